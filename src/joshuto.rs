@@ -2,17 +2,31 @@ extern crate ncurses;
 
 use std;
 use std::collections::HashMap;
-use std::time;
+use std::env;
+use std::path;
+use std::process;
+use std::sync::RwLock;
 
 pub mod config;
 
 mod command;
+mod completion;
+mod conflict;
 mod context;
 mod history;
+mod input_history;
+mod job;
+mod journal;
+mod pager;
 mod preview;
+mod register;
 mod sort;
 mod structs;
+#[cfg(test)]
+mod test_helpers;
 mod textfield;
+mod transfer;
+mod trash;
 mod ui;
 mod unix;
 mod window;
@@ -20,12 +34,42 @@ mod window;
 use self::config::JoshutoTheme;
 use self::config::JoshutoMimetype;
 use self::context::JoshutoContext;
+use self::register::JoshutoRegisters;
 use self::command::CommandKeybind;
 use self::command::JoshutoCommand;
 
+/* what to do at startup, from the command line */
+#[derive(Debug)]
+pub struct JoshutoArgs {
+    /* one tab per directory, the current directory if empty */
+    pub paths: Vec<path::PathBuf>,
+    /* command lines run once the tabs are open */
+    pub commands: Vec<String>,
+    /* file to write the current directory to on quit */
+    pub choosedir: Option<path::PathBuf>,
+    /* file to write the selected files to when opening them, instead of opening */
+    pub choosefiles: Option<path::PathBuf>,
+    /* separate chosen files with NUL instead of newlines */
+    pub choose_null: bool,
+}
+
 lazy_static! {
-    static ref theme_t: JoshutoTheme = JoshutoTheme::get_config();
-    static ref mimetype_t: JoshutoMimetype = JoshutoMimetype::get_config();
+    /* behind a lock so load_config and reload_config can replace them */
+    static ref theme_t: RwLock<JoshutoTheme> = RwLock::new(JoshutoTheme::new());
+    static ref mimetype_t: RwLock<JoshutoMimetype> = RwLock::new(JoshutoMimetype::new());
+}
+
+/* read all four config files, before the screen is set up.
+ * errors are kept to show once it is */
+pub fn load_config(errors: &mut Vec<config::ConfigError>)
+        -> (config::JoshutoConfig, config::JoshutoKeymap)
+{
+    config::parse::check_config_dir(errors);
+    let config_t = config::JoshutoConfig::load(errors);
+    let keymap_t = config::JoshutoKeymap::load(&config_t.aliases, errors);
+    *theme_t.write().unwrap() = JoshutoTheme::load(errors);
+    *mimetype_t.write().unwrap() = JoshutoMimetype::load(errors);
+    (config_t, keymap_t)
 }
 
 fn recurse_get_keycommand<'a>(keymap: &'a HashMap<i32, CommandKeybind>)
@@ -42,15 +86,19 @@ fn recurse_get_keycommand<'a>(keymap: &'a HashMap<i32, CommandKeybind>)
 
         let mut display_vec: Vec<String> = Vec::with_capacity(keymap_len);
         for (key, val) in keymap {
-            display_vec.push(format!("  {}\t{}", *key as u8 as char, val));
+            display_vec.push(format!("  {}\t{}", config::keymap::key_to_string(*key), val));
         }
         display_vec.sort();
 
+        ncurses::keypad(win.win, true);
         win.move_to_top();
         ui::display_options(&win, &display_vec);
         ncurses::doupdate();
 
-        ch = ncurses::wgetch(win.win);
+        ch = match ncurses::wget_wch(win.win) {
+                Some(s) => config::keymap::wch_to_key(s),
+                None => return None,
+            };
     }
     ncurses::doupdate();
 
@@ -71,31 +119,47 @@ fn recurse_get_keycommand<'a>(keymap: &'a HashMap<i32, CommandKeybind>)
     }
 }
 
-fn process_threads(context: &mut JoshutoContext)
+/* update the progress bar and report jobs that are done */
+fn process_jobs(context: &mut JoshutoContext)
 {
-    let wait_duration: time::Duration = time::Duration::from_millis(100);
-    let mut something_finished = false;
-    for i in 0..context.threads.len() {
-        if let Ok(progress_info) = &context.threads[i].0.recv_timeout(wait_duration) {
-            if progress_info.bytes_finished == progress_info.total_bytes {
-                let (_, chandle) = context.threads.remove(i);
-                chandle.join().unwrap();
-                ncurses::werase(context.views.bot_win.win);
-                something_finished = true;
-                break;
+    context.jobs.poll();
+
+    let finished: Vec<(String, bool)> = context.jobs.take_finished().iter()
+            .map(|job| {
+                let mut failed = job.status != job::JobStatus::Finished;
+                let mut msg = format!("Job {} {}: {}", job.id, job.status.as_str(),
+                        job.description);
+                if let job::JobStatus::Failed(e) = &job.status {
+                    msg = format!("{}: {}", msg, e);
+                }
+                if let Some(summary) = job.report.summary() {
+                    msg = format!("{}: {}, press E for details", msg, summary);
+                    failed = true;
+                }
+                (msg, failed)
+            })
+            .collect();
+    for entry in context.jobs.take_journal() {
+        context.journal.push(entry);
+    }
+    if !finished.is_empty() {
+        command::ReloadDirList::reload(context);
+        preview::preview_file(context);
+        for (msg, failed) in finished {
+            if failed {
+                ui::wprint_err(&context.views.bot_win, msg.as_str());
+                context.messages.push(msg);
             } else {
-                let percent = (progress_info.bytes_finished as f64 /
-                        progress_info.total_bytes as f64) as f32;
-                ui::draw_progress_bar(&context.views.bot_win, percent);
-                ncurses::wnoutrefresh(context.views.bot_win.win);
-                ncurses::doupdate();
+                ui::wprint_msg(&context.views.bot_win, msg.as_str());
             }
         }
     }
-    if something_finished {
-        // command::ReloadDirList::reload(context);
-        ncurses::doupdate();
+
+    if let Some(percent) = context.jobs.percentage() {
+        ui::draw_progress_bar(&context.views.bot_win, percent);
+        ncurses::wnoutrefresh(context.views.bot_win.win);
     }
+    ncurses::doupdate();
 }
 
 fn resize_handler(context: &mut JoshutoContext)
@@ -110,55 +174,172 @@ fn resize_handler(context: &mut JoshutoContext)
     ncurses::doupdate();
 }
 
-pub fn run(config_t: config::JoshutoConfig, keymap_t: config::JoshutoKeymap)
+fn open_tabs(paths: &[path::PathBuf], context: &mut JoshutoContext)
+        -> Result<(), command::JoshutoError>
+{
+    if paths.is_empty() {
+        return command::NewTab::new_tab(context);
+    }
+    for path in paths {
+        let curr_path = match path.canonicalize() {
+                Ok(s) => s,
+                Err(e) => {
+                    return Err(command::JoshutoError::new(
+                            format!("{}: {}", path.to_string_lossy(), e)));
+                },
+            };
+        if !curr_path.is_dir() {
+            return Err(command::JoshutoError::new(
+                    format!("{}: Not a directory", path.to_string_lossy())));
+        }
+        command::NewTab::new_tab_at(curr_path, context)?;
+    }
+    if let Err(e) = env::set_current_dir(&context.tabs[0].curr_path) {
+        return Err(command::JoshutoError::new(e.to_string()));
+    }
+    command::TabSwitch::tab_switch(0, context);
+    Ok(())
+}
+
+/* run the startup list from joshuto.toml, then joshuto.rc, then --cmd.
+ * failures and config errors go to the message log instead of stopping the rest */
+fn run_startup(commands: &[String], mut errors: Vec<config::ConfigError>,
+        context: &mut JoshutoContext)
+{
+    let config_errors = errors.len();
+    let mut lines: Vec<(String, String)> = context.config_t.startup.iter()
+            .map(|s| (format!("{} startup", ::CONFIG_FILE), s.clone()))
+            .collect();
+    for rc_line in config::parse::read_rc_files(&mut errors) {
+        lines.push((format!("{}:{}", rc_line.file.to_string_lossy(), rc_line.line),
+                rc_line.command));
+    }
+    lines.extend(commands.iter().map(|s| (String::from("--cmd"), s.clone())));
+
+    let mut failed = errors.len() - config_errors;
+    context.messages.extend(errors.iter().map(|e| e.to_string()));
+    for (source, line) in lines {
+        if let Err(e) = command::CommandLine::run_line(line.as_str(), context) {
+            context.messages.push(format!("{}: {}: {}", source, line, e));
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        ui::wprint_err(&context.views.bot_win,
+                format!("{} startup command(s) failed, see :messages", failed).as_str());
+    } else if config_errors > 0 {
+        ui::wprint_err(&context.views.bot_win,
+                format!("{} config error(s), see :messages", config_errors).as_str());
+    }
+    ncurses::doupdate();
+}
+
+pub fn run(config_t: config::JoshutoConfig, keymap_t: config::JoshutoKeymap,
+        config_errors: Vec<config::ConfigError>, args: JoshutoArgs)
 {
     ui::init_ncurses();
     ncurses::doupdate();
 
-    let mut context = context::JoshutoContext::new(config_t);
-    command::NewTab::new_tab(&mut context);
+    let mut context = context::JoshutoContext::new(config_t, keymap_t);
+    context.choosedir = args.choosedir;
+    context.choosefiles = args.choosefiles;
+    context.choose_null = args.choose_null;
+    if let Err(e) = open_tabs(&args.paths, &mut context) {
+        ui::end_ncurses();
+        eprintln!("{}", e);
+        process::exit(1);
+    }
     ncurses::doupdate();
 
-    while let Some(ch) = ncurses::get_wch() {
-        let ch = match ch {
-                ncurses::WchResult::Char(s) => s as i32,
-                ncurses::WchResult::KeyCode(s) => s,
+    run_startup(&args.commands, config_errors, &mut context);
+
+    /* vim style count typed before a keybinding */
+    let mut count: Option<usize> = None;
+
+    loop {
+        /* every pass, as jobs can also be reaped by the jobs panel */
+        process_jobs(&mut context);
+
+        /* wake up regularly while jobs run, to show their progress */
+        if context.jobs.is_running() {
+            ncurses::timeout(job::JOB_POLL_MILLIS);
+        } else {
+            ncurses::timeout(-1);
+        }
+
+        let ch = match ncurses::get_wch() {
+                Some(s) => config::keymap::wch_to_key(s),
+                None => continue,
             };
 
-        if ch == ncurses::KEY_RESIZE {
+        if ch == config::keymap::keycode(ncurses::KEY_RESIZE) {
             context.views.resize_views();
             resize_handler(&mut context);
             continue;
         }
 
-        if context.threads.len() > 0 {
-            ncurses::timeout(0);
-            process_threads(&mut context);
-        } else {
+        if context.jobs.is_running() {
+            process_jobs(&mut context);
+        }
+
+        /* 0 can only continue a count, so it stays usable as a keybinding */
+        if (ch >= '1' as i32 && ch <= '9' as i32) || (ch == '0' as i32 && count.is_some()) {
+            let digit = (ch - '0' as i32) as usize;
+            let new_count = count.unwrap_or(0).saturating_mul(10).saturating_add(digit);
+            count = Some(new_count);
+            ui::wprint_pending(&context.views.bot_win, context.pending_register, count);
+            ncurses::doupdate();
+            continue;
+        }
+
+        /* "x picks the register for the next keybinding */
+        if ch == '"' as i32 {
             ncurses::timeout(-1);
+            let name = match ncurses::get_wch() {
+                    Some(ncurses::WchResult::Char(s)) => std::char::from_u32(s),
+                    _ => None,
+                };
+            match name {
+                Some(s) if JoshutoRegisters::is_valid_name(s) => {
+                    context.pending_register = Some(s);
+                    ui::wprint_pending(&context.views.bot_win, context.pending_register, count);
+                },
+                _ => {
+                    context.pending_register = None;
+                    count = None;
+                    ui::wprint_err(&context.views.bot_win, "Invalid register");
+                },
+            }
+            ncurses::doupdate();
+            continue;
         }
 
-        let keycommand: &std::boxed::Box<dyn JoshutoCommand>;
-
-        match keymap_t.keymaps.get(&ch) {
-            Some(CommandKeybind::CompositeKeybind(m)) => {
-                match recurse_get_keycommand(&m) {
-                    Some(s) => {
-                        keycommand = s;
-                    }
-                    None => {
-                        continue
-                    },
-                }
+        let curr_count = count.take();
+        if curr_count.is_some() || context.pending_register.is_some() {
+            let curr_tab = &context.tabs[context.curr_tab_index];
+            curr_tab.refresh_file_status(&context.views.bot_win);
+            ncurses::doupdate();
+        }
 
-            },
-            Some(CommandKeybind::SimpleKeybind(s)) => {
-                keycommand = s;
-            },
-            None => {
-                continue;
+        let keymap_t = context.keymap_t.clone();
+        let keycommand: Option<&std::boxed::Box<dyn JoshutoCommand>> =
+            match keymap_t.keymaps.get(&ch) {
+                Some(CommandKeybind::CompositeKeybind(m)) => recurse_get_keycommand(&m),
+                Some(CommandKeybind::SimpleKeybind(s)) => Some(s),
+                None => None,
+            };
+        if let Some(keycommand) = keycommand {
+            let result = match curr_count {
+                    Some(s) => keycommand.execute_count(&mut context, s),
+                    None => keycommand.execute(&mut context),
+                };
+            if let Err(e) = result {
+                ui::wprint_err(&context.views.bot_win, e.to_string().as_str());
+                ncurses::doupdate();
+                context.messages.push(e.to_string());
             }
         }
-        keycommand.execute(&mut context);
+        /* a register only applies to the keybinding right after it */
+        context.pending_register = None;
     }
 }
@@ -5,9 +5,14 @@ use std::collections::HashMap;
 use std::fmt;
 use std::path;
 
+use joshuto::conflict::ConflictPolicy;
 use joshuto::context::JoshutoContext;
+use joshuto::register::JoshutoRegisters;
 use joshuto::structs;
 
+#[cfg(test)]
+mod test;
+
 mod quit;
 pub use self::quit::Quit;
 
@@ -18,8 +23,24 @@ mod open_file;
 pub use self::open_file::OpenFile;
 pub use self::open_file::OpenFileWith;
 
+mod chain;
+pub use self::chain::CommandChain;
+
 mod change_directory;
 pub use self::change_directory::ChangeDirectory;
+
+mod command_line;
+pub use self::command_line::CommandLine;
+
+mod help;
+pub use self::help::Help;
+
+mod registers;
+pub use self::registers::ShowRegisters;
+
+mod reload_config;
+pub use self::reload_config::ReloadConfig;
+
 mod reload_dir;
 pub use self::reload_dir::ReloadDirList;
 
@@ -34,6 +55,8 @@ mod file_operation;
 pub use self::file_operation::CutFiles;
 pub use self::file_operation::CopyFiles;
 pub use self::file_operation::PasteFiles;
+pub use self::file_operation::PasteLink;
+pub use self::file_operation::LinkKind;
 
 mod delete_file;
 pub use self::delete_file::DeleteFiles;
@@ -42,12 +65,22 @@ mod rename_file;
 pub use self::rename_file::RenameFile;
 pub use self::rename_file::RenameFileMethod;
 
+mod jobs;
+pub use self::jobs::ShowJobs;
+pub use self::jobs::JobErrors;
+
+mod messages;
+pub use self::messages::Messages;
+
 mod new_directory;
 pub use self::new_directory::NewDirectory;
 
 mod search;
 pub use self::search::Search;
 
+mod shell;
+pub use self::shell::ShellCommand;
+
 mod show_hidden;
 pub use self::show_hidden::ToggleHiddenFiles;
 
@@ -64,6 +97,14 @@ pub use self::tab_switch::TabSwitch;
 mod set_mode;
 pub use self::set_mode::SetMode;
 
+mod undo;
+pub use self::undo::Undo;
+pub use self::undo::Redo;
+
+mod trash_files;
+pub use self::trash_files::TrashFiles;
+pub use self::trash_files::ShowTrash;
+
 
 #[derive(Debug)]
 pub enum CommandKeybind {
@@ -72,7 +113,15 @@ pub enum CommandKeybind {
 }
 
 pub trait JoshutoRunnable {
-    fn execute(&self, context: &mut JoshutoContext);
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>;
+
+    /* run with a count typed before the keybinding,
+     * commands that don't take a count ignore it */
+    fn execute_count(&self, context: &mut JoshutoContext, _count: usize)
+            -> Result<(), JoshutoError>
+    {
+        self.execute(context)
+    }
 }
 
 pub trait JoshutoCommand: JoshutoRunnable + std::fmt::Display + std::fmt::Debug {}
@@ -86,156 +135,397 @@ impl std::fmt::Display for CommandKeybind {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct ProgressInfo {
-    pub bytes_finished: u64,
-    pub total_bytes: u64,
+#[derive(Debug)]
+pub struct JoshutoError {
+    error: String,
+}
+
+impl JoshutoError {
+    pub fn new(error: String) -> Self
+    {
+        JoshutoError {
+            error,
+        }
+    }
+}
+
+impl std::fmt::Display for JoshutoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        f.write_str(self.error.as_str())
+    }
 }
 
-pub fn from_args(command: &str, args: Option<&Vec<String>>) -> Option<Box<dyn JoshutoCommand>>
+impl std::convert::From<std::io::Error> for JoshutoError {
+    fn from(err: std::io::Error) -> Self
+    {
+        JoshutoError::new(err.to_string())
+    }
+}
+
+#[derive(Debug)]
+pub struct KeymapError {
+    pub command: Option<&'static str>,
+    pub error: String,
+}
+
+impl KeymapError {
+    pub fn new(command: Option<&'static str>, error: String) -> Self
+    {
+        KeymapError {
+            command,
+            error,
+        }
+    }
+}
+
+impl std::fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self.command {
+            Some(s) => write!(f, "{}: {}", s, self.error),
+            None => f.write_str(self.error.as_str()),
+        }
+    }
+}
+
+/* commands known to from_args, used for completion */
+pub const COMMANDS: &[&str] = &[
+    "cd",
+    "chain",
+    "close_tab",
+    "console",
+    "copy_files",
+    "cursor_move",
+    "cursor_move_end",
+    "cursor_move_home",
+    "cursor_move_page_down",
+    "cursor_move_page_up",
+    "cut_files",
+    "delete_files",
+    "help",
+    "job_errors",
+    "jobs",
+    "messages",
+    "mkdir",
+    "new_tab",
+    "open_file",
+    "open_file_with",
+    "parent_directory",
+    "paste_files",
+    "paste_hardlink",
+    "paste_symlink",
+    "quit",
+    "redo",
+    "registers",
+    "reload_config",
+    "reload_dir_list",
+    "rename_file",
+    "search",
+    "select_files",
+    "set_mode",
+    "shell",
+    "tab_switch",
+    "toggle_hidden",
+    "trash",
+    "trash_files",
+    "undo",
+];
+
+/* options accepted by a command, used for completion */
+pub fn command_args(command: &str) -> &'static [&'static str]
+{
+    match command {
+        "paste_files" => &["overwrite=true", "overwrite=false",
+                "skip_exist=true", "skip_exist=false", "conflict=ask",
+                "conflict=overwrite", "conflict=skip", "conflict=rename"],
+        "paste_hardlink" => &["conflict=ask", "conflict=overwrite",
+                "conflict=skip", "conflict=rename"],
+        "paste_symlink" => &["relative=true", "relative=false", "conflict=ask",
+                "conflict=overwrite", "conflict=skip", "conflict=rename"],
+        "rename_file" => &["append", "prepend", "overwrite"],
+        "select_files" => &["toggle=true", "toggle=false",
+                "all=true", "all=false"],
+        "shell" => &["-b", "-p", "-w", "%s", "%f", "%d", "%n"],
+        _ => &[],
+    }
+}
+
+fn parse_bool_arg(command: &'static str, arg: &str, val: &str) -> Result<bool, KeymapError>
+{
+    match val.parse::<bool>() {
+        Ok(s) => Ok(s),
+        Err(_) => Err(KeymapError::new(Some(command),
+                format!("Failed to parse: {}", arg))),
+    }
+}
+
+fn parse_register_arg(command: &'static str, arg: &str, val: &str) -> Result<char, KeymapError>
+{
+    let mut chars = val.chars();
+    match (chars.next(), chars.next()) {
+        (Some(s), None) if JoshutoRegisters::is_valid_name(s) => Ok(s),
+        _ => Err(KeymapError::new(Some(command), format!("Invalid register: {}", arg))),
+    }
+}
+
+/* conflict=x and register=x, which every paste command takes.
+ * false if arg is neither */
+fn parse_paste_arg(command: &'static str, arg: &str, key: &str, val: &str,
+        conflict: &mut Option<ConflictPolicy>, register: &mut Option<char>)
+        -> Result<bool, KeymapError>
+{
+    match key {
+        "conflict" => {
+            match ConflictPolicy::parse(val) {
+                Some(s) => *conflict = Some(s),
+                None => {
+                    return Err(KeymapError::new(Some(command),
+                            format!("Unknown conflict policy: {}", arg)));
+                },
+            }
+        },
+        "register" => *register = Some(parse_register_arg(command, arg, val)?),
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/* paste_symlink also takes relative=true */
+fn parse_paste_link(command: &'static str, mut kind: LinkKind, args: &[String])
+        -> Result<Box<dyn JoshutoCommand>, KeymapError>
+{
+    let mut conflict: Option<ConflictPolicy> = None;
+    let mut register: Option<char> = None;
+    for arg in args {
+        let splitarg: Vec<&str> = arg.split('=').collect();
+        if splitarg.len() != 2 {
+            return Err(KeymapError::new(Some(command), format!("Unknown option: {}", arg)));
+        }
+        if splitarg[0] == "relative" && kind != LinkKind::Hardlink {
+            if parse_bool_arg(command, arg, splitarg[1])? {
+                kind = LinkKind::RelativeSymlink;
+            }
+        } else if !parse_paste_arg(command, arg, splitarg[0], splitarg[1],
+                &mut conflict, &mut register)? {
+            return Err(KeymapError::new(Some(command), format!("Unknown option: {}", arg)));
+        }
+    }
+    Ok(Box::new(self::PasteLink::new(kind, conflict, register)))
+}
+
+/* register=x is the only argument copy_files and cut_files take */
+fn parse_register_args(command: &'static str, args: &[String])
+        -> Result<Option<char>, KeymapError>
+{
+    let mut register: Option<char> = None;
+    for arg in args {
+        let splitarg: Vec<&str> = arg.split('=').collect();
+        if splitarg.len() == 2 && splitarg[0] == "register" {
+            register = Some(parse_register_arg(command, arg, splitarg[1])?);
+        } else {
+            return Err(KeymapError::new(Some(command), format!("Unknown option: {}", arg)));
+        }
+    }
+    Ok(register)
+}
+
+/* aliases expanding into other aliases stop after this many steps */
+const MAX_ALIAS_DEPTH: usize = 16;
+
+pub fn from_args(command: &str, args: &[String], aliases: &HashMap<String, String>)
+        -> Result<Box<dyn JoshutoCommand>, KeymapError>
+{
+    from_args_(command, args, aliases, 0)
+}
+
+fn expand_alias(command: &str, args: &[String], aliases: &HashMap<String, String>,
+        depth: usize) -> Result<Box<dyn JoshutoCommand>, KeymapError>
+{
+    let alias = match aliases.get(command) {
+        Some(s) => s,
+        None => return Err(KeymapError::new(None, format!("Unknown command: {}", command))),
+    };
+    if depth >= MAX_ALIAS_DEPTH {
+        return Err(KeymapError::new(None, format!("Alias loop: {}", command)));
+    }
+
+    let mut alias_args: Vec<String> = split_shell_style(alias).iter()
+            .map(|s| s.to_string()).collect();
+    if alias_args.is_empty() {
+        return Err(KeymapError::new(None, format!("Empty alias: {}", command)));
+    }
+    alias_args.extend_from_slice(args);
+    from_args_(alias_args[0].as_str(), &alias_args[1..], aliases, depth + 1)
+}
+
+/* the console has already split the line into words, so only ~ and
+ * variables are left for wordexp to expand, not spaces, quotes or globs */
+fn escape_wordexp(word: &str) -> String
+{
+    let mut escaped = String::with_capacity(word.len());
+    for ch in word.chars() {
+        if ch.is_whitespace() || "'\"\\*?[|&;<>()".contains(ch) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+fn from_args_(command: &str, args: &[String], aliases: &HashMap<String, String>,
+        depth: usize) -> Result<Box<dyn JoshutoCommand>, KeymapError>
 {
     match command {
         "cd" => {
-            if let Some(args) = args {
-                match wordexp::wordexp(args[0].as_str(), 0) {
-                    Ok(exp_strs) => {
-                        for exp_str in exp_strs {
-                            let path = path::PathBuf::from(exp_str);
-                            return Some(Box::new(self::ChangeDirectory::new(path)));
-                        }
-                    },
-                    Err(_) => {
-                        eprintln!("Failed to parse: {:?}", args[0]);
+            if args.is_empty() {
+                return Err(KeymapError::new(Some("cd"), String::from("Expected 1 argument")));
+            }
+            match wordexp::wordexp(escape_wordexp(args[0].as_str()).as_str(), 0) {
+                Ok(exp_strs) => {
+                    if let Some(exp_str) = exp_strs.into_iter().next() {
+                        let path = path::PathBuf::from(exp_str);
+                        return Ok(Box::new(self::ChangeDirectory::new(path)));
                     }
+                    Err(KeymapError::new(Some("cd"), format!("Failed to parse: {}", args[0])))
+                },
+                Err(_) => {
+                    Err(KeymapError::new(Some("cd"), format!("Failed to parse: {}", args[0])))
                 }
             }
-            return None;
         },
-        "close_tab" => Some(Box::new(self::CloseTab::new())),
-        "copy_files" => Some(Box::new(self::CopyFiles::new())),
+        "chain" => Ok(Box::new(self::CommandChain::from_lines_(args, aliases, depth)?)),
+        "close_tab" => Ok(Box::new(self::CloseTab::new())),
+        "console" => Ok(Box::new(self::CommandLine::new(
+                args.join(" "), String::new()))),
+        "copy_files" => {
+            let register = parse_register_args("copy_files", args)?;
+            Ok(Box::new(self::CopyFiles::new(register)))
+        },
         "cursor_move" => {
-            if let Some(args) = args {
-                if args.len() > 0 {
-                    match args[0].parse::<i32>() {
-                        Ok(s) => {
-                            return Some(Box::new(self::CursorMove::new(s)));
-                        },
-                        Err(e) => {
-                            eprintln!("{}", e);
-                        },
-                    }
-                }
+            if args.is_empty() {
+                return Err(KeymapError::new(Some("cursor_move"), String::from("Expected 1 argument")));
             }
-            return None;
+            match args[0].parse::<i32>() {
+                Ok(s) => Ok(Box::new(self::CursorMove::new(s))),
+                Err(e) => Err(KeymapError::new(Some("cursor_move"), e.to_string())),
+            }
+        },
+        "cursor_move_home" => Ok(Box::new(self::CursorMoveHome::new())),
+        "cursor_move_end" => Ok(Box::new(self::CursorMoveEnd::new())),
+        "cursor_move_page_up" => Ok(Box::new(self::CursorMovePageUp::new())),
+        "cursor_move_page_down" => Ok(Box::new(self::CursorMovePageDown::new())),
+        "cut_files" => {
+            let register = parse_register_args("cut_files", args)?;
+            Ok(Box::new(self::CutFiles::new(register)))
         },
-        "cursor_move_home" => Some(Box::new(self::CursorMoveHome::new())),
-        "cursor_move_end" => Some(Box::new(self::CursorMoveEnd::new())),
-        "cursor_move_page_up" => Some(Box::new(self::CursorMovePageUp::new())),
-        "cursor_move_page_down" => Some(Box::new(self::CursorMovePageDown::new())),
-        "cut_files" => Some(Box::new(self::CutFiles::new())),
-        "delete_files" => Some(Box::new(self::DeleteFiles::new())),
-        "mkdir" => Some(Box::new(self::NewDirectory::new())),
-        "new_tab" => Some(Box::new(self::NewTab::new())),
-        "open_file" => Some(Box::new(self::OpenFile::new())),
-        "open_file_with" => Some(Box::new(self::OpenFileWith::new())),
-        "parent_directory" => Some(Box::new(self::ParentDirectory::new())),
+        "delete_files" => Ok(Box::new(self::DeleteFiles::new())),
+        "help" => Ok(Box::new(self::Help::new())),
+        "job_errors" => Ok(Box::new(self::JobErrors::new())),
+        "jobs" => Ok(Box::new(self::ShowJobs::new())),
+        "messages" => Ok(Box::new(self::Messages::new())),
+        "mkdir" => Ok(Box::new(self::NewDirectory::new())),
+        "new_tab" => Ok(Box::new(self::NewTab::new())),
+        "open_file" => Ok(Box::new(self::OpenFile::new())),
+        "open_file_with" => Ok(Box::new(self::OpenFileWith::new())),
+        "parent_directory" => Ok(Box::new(self::ParentDirectory::new())),
         "paste_files" => {
-            let mut options = fs_extra::dir::CopyOptions::new();
-            if let Some(args) = args {
-                for arg in args {
-                    let splitarg: Vec<&str> = arg.split('=').collect();
-                    if splitarg.len() == 2 {
-                        match splitarg[0] {
-                            "overwrite" => {
-                                if let Ok(s) = splitarg[1].parse::<bool>() {
-                                    options.overwrite = s;
-                                } else {
-                                    eprintln!("Failed to parse: {}", arg);
-                                }
-                            },
-                            "skip_exist" => {
-                                if let Ok(s) = splitarg[1].parse::<bool>() {
-                                    options.skip_exist = s;
-                                } else {
-                                    eprintln!("Failed to parse: {}", arg);
-                                }
-                            },
-                            _ => {},
+            let mut conflict: Option<ConflictPolicy> = None;
+            let mut register: Option<char> = None;
+            for arg in args {
+                let splitarg: Vec<&str> = arg.split('=').collect();
+                if splitarg.len() != 2 {
+                    return Err(KeymapError::new(Some("paste_files"),
+                            format!("Unknown option: {}", arg)));
+                }
+                match splitarg[0] {
+                    "overwrite" => {
+                        if parse_bool_arg("paste_files", arg, splitarg[1])? {
+                            conflict = Some(ConflictPolicy::Overwrite);
                         }
-                    }
+                    },
+                    "skip_exist" => {
+                        if parse_bool_arg("paste_files", arg, splitarg[1])? {
+                            conflict = Some(ConflictPolicy::Skip);
+                        }
+                    },
+                    key => {
+                        if !parse_paste_arg("paste_files", arg, key, splitarg[1],
+                                &mut conflict, &mut register)? {
+                            return Err(KeymapError::new(Some("paste_files"),
+                                    format!("Unknown option: {}", arg)));
+                        }
+                    },
                 }
             }
-            let paste = self::PasteFiles::new(options);
-            Some(Box::new(paste))
+            let paste = self::PasteFiles::new(conflict, register);
+            Ok(Box::new(paste))
         },
-        "quit" => Some(Box::new(self::Quit::new())),
-        "reload_dir_list" => Some(Box::new(self::ReloadDirList::new())),
+        "paste_hardlink" => parse_paste_link("paste_hardlink", LinkKind::Hardlink, args),
+        "paste_symlink" => parse_paste_link("paste_symlink", LinkKind::Symlink, args),
+        "quit" => Ok(Box::new(self::Quit::new())),
+        "redo" => Ok(Box::new(self::Redo::new())),
+        "registers" => Ok(Box::new(self::ShowRegisters::new())),
+        "reload_config" => Ok(Box::new(self::ReloadConfig::new())),
+        "reload_dir_list" => Ok(Box::new(self::ReloadDirList::new())),
         "rename_file" => {
-            let method: RenameFileMethod;
-            if let Some(args) = args {
-                if args.len() > 0 {
-                    method = match args[0].as_str() {
+            let method: RenameFileMethod = match args.first() {
+                Some(s) => {
+                    match s.as_str() {
                         "prepend" => self::RenameFileMethod::Prepend,
                         "overwrite" => self::RenameFileMethod::Overwrite,
                         "append" => self::RenameFileMethod::Append,
-                        _ => self::RenameFileMethod::Append,
-                    };
-                } else {
-                    method = self::RenameFileMethod::Append;
-                }
-            } else {
-                method = self::RenameFileMethod::Append;
-            }
-            Some(Box::new(self::RenameFile::new(method)))
+                        _ => {
+                            return Err(KeymapError::new(Some("rename_file"),
+                                    format!("Unknown option: {}", s)));
+                        },
+                    }
+                },
+                None => self::RenameFileMethod::Append,
+            };
+            Ok(Box::new(self::RenameFile::new(method)))
         },
-        "search" => Some(Box::new(self::Search::new())),
+        "search" => Ok(Box::new(self::Search::new())),
         "select_files" => {
             let mut toggle = false;
             let mut all = false;
-            if let Some(args) = args {
-                for arg in args {
-                    let splitarg: Vec<&str> = arg.split('=').collect();
-                    if splitarg.len() == 2 {
-                        match splitarg[0] {
-                            "toggle" => {
-                                if let Ok(s) = splitarg[1].parse::<bool>() {
-                                    toggle = s;
-                                } else {
-                                    eprintln!("Failed to parse: {}", arg);
-                                }
-                            },
-                            "all" => {
-                                if let Ok(s) = splitarg[1].parse::<bool>() {
-                                    all = s;
-                                } else {
-                                    eprintln!("Failed to parse: {}", arg);
-                                }
-                            },
-                            _ => {},
-                        }
-                    }
+            for arg in args {
+                let splitarg: Vec<&str> = arg.split('=').collect();
+                if splitarg.len() != 2 {
+                    return Err(KeymapError::new(Some("select_files"),
+                            format!("Unknown option: {}", arg)));
+                }
+                match splitarg[0] {
+                    "toggle" => {
+                        toggle = parse_bool_arg("select_files", arg, splitarg[1])?;
+                    },
+                    "all" => {
+                        all = parse_bool_arg("select_files", arg, splitarg[1])?;
+                    },
+                    _ => {
+                        return Err(KeymapError::new(Some("select_files"),
+                                format!("Unknown option: {}", arg)));
+                    },
                 }
             }
-            Some(Box::new(self::SelectFiles::new(toggle, all)))
+            Ok(Box::new(self::SelectFiles::new(toggle, all)))
         },
-        "set_mode" => Some(Box::new(self::SetMode::new())),
+        "set_mode" => Ok(Box::new(self::SetMode::new())),
+        "shell" => Ok(Box::new(self::ShellCommand::from_args(args)?)),
         "tab_switch" => {
-            if let Some(args) = args {
-                if args.len() > 0 {
-                    match args[0].parse::<i32>() {
-                        Ok(s) => {
-                            return Some(Box::new(self::TabSwitch::new(s)));
-                        },
-                        Err(e) => {
-                            eprintln!("{}", e);
-                        },
-                    }
-                }
+            if args.is_empty() {
+                return Err(KeymapError::new(Some("tab_switch"), String::from("Expected 1 argument")));
+            }
+            match args[0].parse::<i32>() {
+                Ok(s) => Ok(Box::new(self::TabSwitch::new(s))),
+                Err(e) => Err(KeymapError::new(Some("tab_switch"), e.to_string())),
             }
-            return None;
         },
-        "toggle_hidden" => Some(Box::new(self::ToggleHiddenFiles::new())),
-        _ => None,
+        "toggle_hidden" => Ok(Box::new(self::ToggleHiddenFiles::new())),
+        "trash" => Ok(Box::new(self::ShowTrash::new())),
+        "trash_files" => Ok(Box::new(self::TrashFiles::new())),
+        "undo" => Ok(Box::new(self::Undo::new())),
+        _ => expand_alias(command, args, aliases, depth),
     }
 }
 
@@ -256,8 +546,7 @@ pub fn collect_selected_paths(dirlist: &structs::JoshutoDirList)
     }
 }
 
-#[allow(dead_code)]
-pub fn split_shell_style(line: &String) -> Vec<&str>
+pub fn split_shell_style(line: &str) -> Vec<&str>
 {
     let mut args: Vec<&str> = Vec::new();
     let mut char_ind = line.char_indices();
@@ -267,28 +556,33 @@ pub fn split_shell_style(line: &String) -> Vec<&str>
             continue;
         }
         if ch == '\'' {
-            while let Some((j, ch)) = char_ind.next() {
+            let mut end = line.len();
+            for (j, ch) in char_ind.by_ref() {
                 if ch == '\'' {
-                    args.push(&line[i+1..j]);
+                    end = j;
                     break;
                 }
             }
+            args.push(&line[i+1..end]);
         } else if ch == '"'{
-            while let Some((j, ch)) = char_ind.next() {
+            let mut end = line.len();
+            for (j, ch) in char_ind.by_ref() {
                 if ch == '"' {
-                    args.push(&line[i+1..j]);
+                    end = j;
                     break;
                 }
             }
+            args.push(&line[i+1..end]);
         } else {
-            while let Some((j, ch)) = char_ind.next() {
+            let mut end = line.len();
+            for (j, ch) in char_ind.by_ref() {
                 if ch.is_whitespace() {
-                    args.push(&line[i..j]);
+                    end = j;
                     break;
                 }
             }
+            args.push(&line[i..end]);
         }
     }
     args
 }
-
@@ -0,0 +1,85 @@
+use std;
+use std::collections::HashMap;
+
+use joshuto::command;
+use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
+use joshuto::command::JoshutoRunnable;
+use joshuto::command::KeymapError;
+use joshuto::context::JoshutoContext;
+
+#[derive(Debug)]
+pub struct CommandChain {
+    commands: Vec<Box<dyn JoshutoCommand>>,
+}
+
+impl CommandChain {
+    pub fn new(commands: Vec<Box<dyn JoshutoCommand>>) -> Self
+    {
+        CommandChain {
+            commands,
+        }
+    }
+    pub const fn command() -> &'static str { "chain" }
+
+    /* each line is a command followed by its arguments */
+    pub fn from_lines(lines: &[String], aliases: &HashMap<String, String>)
+            -> Result<Self, KeymapError>
+    {
+        Self::from_lines_(lines, aliases, 0)
+    }
+
+    pub fn from_lines_(lines: &[String], aliases: &HashMap<String, String>, depth: usize)
+            -> Result<Self, KeymapError>
+    {
+        if lines.is_empty() {
+            return Err(KeymapError::new(Some(Self::command()),
+                    String::from("Expected at least 1 command")));
+        }
+        let mut commands: Vec<Box<dyn JoshutoCommand>> = Vec::with_capacity(lines.len());
+        for (i, line) in lines.iter().enumerate() {
+            let args: Vec<String> = command::split_shell_style(line).iter()
+                    .map(|s| s.to_string()).collect();
+            if args.is_empty() {
+                return Err(KeymapError::new(Some(Self::command()),
+                        format!("step {}: empty command", i + 1)));
+            }
+            match command::from_args_(args[0].as_str(), &args[1..], aliases, depth) {
+                Ok(s) => commands.push(s),
+                Err(e) => {
+                    return Err(KeymapError::new(Some(Self::command()),
+                            format!("step {}: {}", i + 1, e)));
+                },
+            }
+        }
+        Ok(CommandChain::new(commands))
+    }
+}
+
+impl JoshutoCommand for CommandChain {}
+
+impl std::fmt::Display for CommandChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        for (i, command) in self.commands.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{}", command)?;
+        }
+        Ok(())
+    }
+}
+
+impl JoshutoRunnable for CommandChain {
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        for (i, command) in self.commands.iter().enumerate() {
+            if let Err(e) = command.execute(context) {
+                return Err(JoshutoError::new(format!("{} failed at step {} ({}): {}",
+                        Self::command(), i + 1, command, e)));
+            }
+        }
+        Ok(())
+    }
+}
@@ -2,13 +2,12 @@ extern crate ncurses;
 
 use std;
 use std::path;
-use std::process;
 
 use joshuto::context::JoshutoContext;
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
 use joshuto::preview;
-use joshuto::ui;
 
 #[derive(Clone, Debug)]
 pub struct ChangeDirectory {
@@ -25,11 +24,10 @@ impl ChangeDirectory {
     pub const fn command() -> &'static str { "cd" }
 
     pub fn change_directory(path: &path::PathBuf, context: &mut JoshutoContext)
+            -> Result<(), JoshutoError>
     {
         if !path.exists() {
-            ui::wprint_err(&context.views.bot_win, "Error: No such file or directory");
-            ncurses::doupdate();
-            return;
+            return Err(JoshutoError::new(String::from("Error: No such file or directory")));
         }
         let curr_tab = &mut context.tabs[context.curr_tab_index];
 
@@ -38,40 +36,22 @@ impl ChangeDirectory {
         let curr_list = curr_tab.curr_list.take();
         curr_tab.history.put_back(curr_list);
 
-        match std::env::set_current_dir(path.as_path()) {
-            Ok(_) => {
-                curr_tab.curr_path = path.clone();
-            },
-            Err(e) => {
-                ui::wprint_err(&context.views.bot_win, e.to_string().as_str());
-                return;
-            }
-        }
+        std::env::set_current_dir(path.as_path())?;
+        curr_tab.curr_path = path.clone();
         curr_tab.history.populate_to_root(&curr_tab.curr_path, &context.config_t.sort_type);
 
-        curr_tab.curr_list = match curr_tab.history.pop_or_create(&curr_tab.curr_path,
-                    &context.config_t.sort_type) {
-            Ok(s) => {
-                Some(s)
-            },
-            Err(e) => {
-                eprintln!("{}", e);
-                process::exit(1);
-            },
-        };
+        let curr_list = curr_tab.history.pop_or_create(&curr_tab.curr_path,
+                    &context.config_t.sort_type)?;
+        curr_tab.curr_list = Some(curr_list);
 
         if let Some(parent) = curr_tab.curr_path.parent() {
-            curr_tab.parent_list = match curr_tab.history.pop_or_create(&parent, &context.config_t.sort_type) {
-                Ok(s) => { Some(s) },
-                Err(e) => {
-                    eprintln!("{}", e);
-                    process::exit(1);
-                },
-            };
+            let parent_list = curr_tab.history.pop_or_create(&parent, &context.config_t.sort_type)?;
+            curr_tab.parent_list = Some(parent_list);
         }
 
         curr_tab.refresh(&context.views, &context.config_t,
             &context.username, &context.hostname);
+        Ok(())
     }
 }
 
@@ -85,10 +65,11 @@ impl std::fmt::Display for ChangeDirectory {
 }
 
 impl JoshutoRunnable for ChangeDirectory {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
-        Self::change_directory(&self.path, context);
+        Self::change_directory(&self.path, context)?;
         preview::preview_file(context);
         ncurses::doupdate();
+        Ok(())
     }
 }
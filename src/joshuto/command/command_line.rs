@@ -0,0 +1,93 @@
+extern crate ncurses;
+
+use std;
+
+use joshuto::command;
+use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
+use joshuto::command::JoshutoRunnable;
+use joshuto::completion::CompletionKind;
+use joshuto::completion::JoshutoCompleter;
+use joshuto::context::JoshutoContext;
+use joshuto::textfield::JoshutoTextField;
+use joshuto::ui;
+
+#[derive(Clone, Debug)]
+pub struct CommandLine {
+    prefix: String,
+    suffix: String,
+}
+
+impl CommandLine {
+    pub fn new(prefix: String, suffix: String) -> Self
+    {
+        CommandLine {
+            prefix,
+            suffix,
+        }
+    }
+    pub const fn command() -> &'static str { "console" }
+
+    pub fn readline(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        const PROMPT: &str = ":";
+        let (term_rows, term_cols) = ui::getmaxyx();
+        let user_input: Option<String>;
+        {
+            let mut textfield = JoshutoTextField::new(1,
+                term_cols, (term_rows as usize - 1, 0), PROMPT.to_string());
+            let curr_path = context.tabs[context.curr_tab_index].curr_path.clone();
+            textfield.set_completer(JoshutoCompleter::new(CompletionKind::Command, curr_path));
+            textfield.set_history(context.input_history.get(Self::command()));
+
+            user_input = textfield.readline_with_initial(&self.prefix, &self.suffix);
+        }
+        ncurses::doupdate();
+
+        let result = match user_input {
+            Some(user_input) => {
+                context.input_history.push(Self::command(), user_input.as_str());
+                Self::run_line(user_input.as_str(), context)
+            },
+            None => {
+                let curr_tab = &context.tabs[context.curr_tab_index];
+                curr_tab.refresh_file_status(&context.views.bot_win);
+                Ok(())
+            },
+        };
+        ncurses::doupdate();
+        result
+    }
+
+    pub fn run_line(line: &str, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        let args: Vec<String> = command::split_shell_style(line).iter()
+                .map(|s| s.to_string()).collect();
+        if args.is_empty() {
+            let curr_tab = &context.tabs[context.curr_tab_index];
+            curr_tab.refresh_file_status(&context.views.bot_win);
+            return Ok(());
+        }
+
+        match command::from_args(args[0].as_str(), &args[1..], &context.config_t.aliases) {
+            Ok(s) => s.execute(context),
+            Err(e) => Err(JoshutoError::new(e.to_string())),
+        }
+    }
+}
+
+impl JoshutoCommand for CommandLine {}
+
+impl std::fmt::Display for CommandLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        write!(f, "{} {}{}", Self::command(), self.prefix, self.suffix)
+    }
+}
+
+impl JoshutoRunnable for CommandLine {
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        self.readline(context)
+    }
+}
@@ -3,6 +3,7 @@ extern crate ncurses;
 use std;
 
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
 use joshuto::context::JoshutoContext;
 use joshuto::preview;
@@ -64,7 +65,13 @@ impl std::fmt::Display for CursorMove {
 }
 
 impl JoshutoRunnable for CursorMove {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        self.execute_count(context, 1)
+    }
+
+    fn execute_count(&self, context: &mut JoshutoContext, count: usize)
+            -> Result<(), JoshutoError>
     {
         let mut movement: Option<i32> = None;
 
@@ -72,12 +79,14 @@ impl JoshutoRunnable for CursorMove {
             let curr_tab = &mut context.tabs[context.curr_tab_index];
             if let Some(curr_list) = curr_tab.curr_list.as_ref() {
                 let curr_index = curr_list.index;
-                movement = Some(curr_index + self.movement);
+                let count = count.min(i32::MAX as usize) as i32;
+                movement = Some(curr_index.saturating_add(self.movement.saturating_mul(count)));
             }
         }
         if let Some(s) = movement {
             CursorMove::cursor_move(s, context);
         }
+        Ok(())
     }
 }
 
@@ -99,7 +108,7 @@ impl std::fmt::Display for CursorMovePageUp {
 }
 
 impl JoshutoRunnable for CursorMovePageUp {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         let mut movement: Option<i32> = None;
 
@@ -108,7 +117,7 @@ impl JoshutoRunnable for CursorMovePageUp {
             if let Some(curr_list) = curr_tab.curr_list.as_ref() {
                 let curr_index = curr_list.index;
                 if curr_index <= 0 {
-                    return;
+                    return Ok(());
                 }
 
                 let half_page = context.views.mid_win.cols / 2;
@@ -118,6 +127,7 @@ impl JoshutoRunnable for CursorMovePageUp {
         if let Some(s) = movement {
             CursorMove::cursor_move(s, context);
         }
+        Ok(())
     }
 }
 
@@ -139,7 +149,7 @@ impl std::fmt::Display for CursorMovePageDown {
 }
 
 impl JoshutoRunnable for CursorMovePageDown {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         let mut movement: Option<i32> = None;
 
@@ -149,7 +159,7 @@ impl JoshutoRunnable for CursorMovePageDown {
                 let curr_index = curr_list.index;
                 let dir_len = curr_list.contents.len();
                 if curr_index >= dir_len as i32 - 1 {
-                    return;
+                    return Ok(());
                 }
 
                 let half_page = context.views.mid_win.cols / 2;
@@ -159,6 +169,7 @@ impl JoshutoRunnable for CursorMovePageDown {
         if let Some(s) = movement {
             CursorMove::cursor_move(s, context);
         }
+        Ok(())
     }
 }
 
@@ -180,7 +191,7 @@ impl std::fmt::Display for CursorMoveHome {
 }
 
 impl JoshutoRunnable for CursorMoveHome {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         let mut movement: Option<i32> = None;
 
@@ -189,7 +200,7 @@ impl JoshutoRunnable for CursorMoveHome {
             if let Some(curr_list) = curr_tab.curr_list.as_ref() {
                 let curr_index = curr_list.index;
                 if curr_index <= 0 {
-                    return;
+                    return Ok(());
                 }
                 movement = Some(0);
             }
@@ -197,6 +208,7 @@ impl JoshutoRunnable for CursorMoveHome {
         if let Some(s) = movement {
             CursorMove::cursor_move(s, context);
         }
+        Ok(())
     }
 }
 
@@ -218,7 +230,7 @@ impl std::fmt::Display for CursorMoveEnd {
 }
 
 impl JoshutoRunnable for CursorMoveEnd {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         let mut movement: Option<i32> = None;
 
@@ -228,7 +240,7 @@ impl JoshutoRunnable for CursorMoveEnd {
                 let curr_index = curr_list.index;
                 let dir_len = curr_list.contents.len();
                 if curr_index >= dir_len as i32 - 1 {
-                    return;
+                    return Ok(());
                 }
                 movement = Some(dir_len as i32 - 1);
             }
@@ -237,5 +249,6 @@ impl JoshutoRunnable for CursorMoveEnd {
         if let Some(s) = movement {
             CursorMove::cursor_move(s, context);
         }
+        Ok(())
     }
 }
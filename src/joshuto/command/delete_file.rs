@@ -1,14 +1,20 @@
 extern crate ncurses;
 
 use std::path;
-use std::fs;
 
 use joshuto::command;
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
 use joshuto::config::keymap;
 use joshuto::context::JoshutoContext;
+use joshuto::job::JobHandle;
+use joshuto::job::JobReport;
+use joshuto::job::JobResult;
+use joshuto::job::ProgressInfo;
+use joshuto::job::ProgressUnit;
 use joshuto::preview;
+use joshuto::transfer;
 use joshuto::ui;
 
 #[derive(Clone, Debug)]
@@ -18,17 +24,22 @@ impl DeleteFiles {
     pub fn new() -> Self { DeleteFiles }
     pub const fn command() -> &'static str { "delete_files" }
 
-    pub fn remove_files(paths: Vec<path::PathBuf>)
+    pub fn remove_files(paths: Vec<path::PathBuf>, handle: JobHandle) -> JobResult
     {
+        let mut report = JobReport::new();
+        let mut progress_info = ProgressInfo {
+                bytes_finished: 0,
+                total_bytes: paths.len() as u64,
+            };
         for path in &paths {
-            if let Ok(metadata) = fs::symlink_metadata(path) {
-                if metadata.is_dir() {
-                    fs::remove_dir_all(&path).unwrap();
-                } else {
-                    fs::remove_file(&path).unwrap();
-                }
+            if !handle.check() {
+                break;
             }
+            transfer::remove_path(path, &mut report);
+            progress_info.bytes_finished += 1;
+            handle.progress(progress_info.clone());
         }
+        Ok(report)
     }
 }
 
@@ -42,7 +53,7 @@ impl std::fmt::Display for DeleteFiles {
 }
 
 impl JoshutoRunnable for DeleteFiles {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         ui::wprint_msg(&context.views.bot_win, "Delete selected files? (Y/n)");
         ncurses::timeout(-1);
@@ -50,17 +61,18 @@ impl JoshutoRunnable for DeleteFiles {
 
         let ch: i32 = ncurses::getch();
         if ch == 'y' as i32 || ch == keymap::ENTER as i32 {
-            if let Some(s) = context.tabs[context.curr_tab_index].curr_list.as_ref() {
-                if let Some(paths) = command::collect_selected_paths(s) {
-                    Self::remove_files(paths);
-                }
+            let paths = match context.tabs[context.curr_tab_index].curr_list.as_ref() {
+                    Some(s) => command::collect_selected_paths(s),
+                    None => None,
+                };
+            if let Some(paths) = paths {
+                let description = format!("delete {} file(s)", paths.len());
+                let total = paths.len() as u64;
+                let id = context.jobs.spawn(description.clone(), ProgressUnit::Files, total,
+                        move |handle| Self::remove_files(paths, handle));
+                ui::wprint_msg(&context.views.bot_win,
+                        format!("Job {} started: {}", id, description).as_str());
             }
-            ui::wprint_msg(&context.views.bot_win, "Deleted files");
-
-            let curr_tab = &mut context.tabs[context.curr_tab_index];
-            curr_tab.reload_contents(&context.config_t.sort_type);
-            curr_tab.refresh(&context.views, &context.config_t,
-                &context.username, &context.hostname);
         } else {
             let curr_tab = &context.tabs[context.curr_tab_index];
             curr_tab.refresh_file_status(&context.views.bot_win);
@@ -68,5 +80,6 @@ impl JoshutoRunnable for DeleteFiles {
                     &context.username, &context.hostname);
         }
         ncurses::doupdate();
+        Ok(())
     }
 }
@@ -2,42 +2,104 @@ extern crate fs_extra;
 extern crate ncurses;
 
 use std;
+use std::fs;
+use std::io;
+use std::os::unix;
 use std::path;
-use std::sync;
-use std::thread;
 
 use joshuto::command;
-use joshuto::command::ProgressInfo;
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
+use joshuto::config::keymap;
+use joshuto::conflict;
+use joshuto::conflict::ConflictPolicy;
+use joshuto::conflict::PasteItem;
 use joshuto::context::JoshutoContext;
+use joshuto::job::JobHandle;
+use joshuto::job::JobReport;
+use joshuto::job::JobResult;
+use joshuto::job::ProgressInfo;
+use joshuto::job::ProgressUnit;
+use joshuto::journal::FileAction;
 use joshuto::preview;
-use joshuto::structs::JoshutoDirList;
+use joshuto::register;
+use joshuto::register::FileOp;
+use joshuto::register::Register;
+use joshuto::transfer;
+use joshuto::transfer::MoveVerify;
+use joshuto::ui;
+use joshuto::unix::relative_path;
+use joshuto::window;
+
+/* an explicit register= argument wins over a "x typed before the keybinding */
+fn register_name(register: Option<char>, context: &JoshutoContext) -> char
+{
+    register.or(context.pending_register).unwrap_or(register::DEFAULT_REGISTER)
+}
 
-lazy_static! {
-    static ref selected_files: sync::Mutex<Vec<path::PathBuf>> = sync::Mutex::new(vec![]);
-    static ref fileop: sync::Mutex<FileOp> = sync::Mutex::new(FileOp::Copy);
+fn yank_selected(register: Option<char>, operation: FileOp, context: &mut JoshutoContext)
+{
+    let name = register_name(register, context);
+    let paths = match context.tabs[context.curr_tab_index].curr_list.as_ref() {
+            Some(s) => command::collect_selected_paths(s),
+            None => None,
+        };
+    if let Some(paths) = paths {
+        ui::wprint_msg(&context.views.bot_win, format!("{} file(s) {} into \"{}",
+                paths.len(), operation.as_str(), name).as_str());
+        ncurses::doupdate();
+        context.registers.set(name, Register { operation, paths });
+    }
 }
 
-fn set_file_op(operation: FileOp)
+fn fmt_register(f: &mut std::fmt::Formatter, register: Option<char>) -> std::fmt::Result
 {
-    let mut data = fileop.lock().unwrap();
-    *data = operation;
+    match register {
+        Some(s) => write!(f, " register={}", s),
+        None => Ok(()),
+    }
 }
 
-fn repopulated_selected_files(dirlist: &JoshutoDirList) -> bool
+fn fmt_conflict(f: &mut std::fmt::Formatter, conflict: Option<ConflictPolicy>) -> std::fmt::Result
 {
-    if let Some(contents) = command::collect_selected_paths(dirlist) {
-        let mut data = selected_files.lock().unwrap();
-        *data = contents;
-        return true;
+    match conflict {
+        Some(ConflictPolicy::Overwrite) => f.write_str(" overwrite=true"),
+        Some(ConflictPolicy::Skip) => f.write_str(" skip_exist=true"),
+        Some(s) => write!(f, " conflict={}", s.as_str()),
+        None => Ok(()),
     }
-    return false;
 }
 
-enum FileOp {
-    Cut,
-    Copy,
+/* where the register's files go when pasted into the current directory,
+ * asking about conflicts if need be. None if there is nothing to paste.
+ * operation decides what pasting a file into its own directory does */
+fn plan_register_paste(context: &mut JoshutoContext, name: char,
+        conflict: Option<ConflictPolicy>, operation: Option<FileOp>)
+        -> Result<Option<(Register, Vec<PasteItem>)>, JoshutoError>
+{
+    let register = match context.registers.get(name) {
+            Some(s) => s.clone(),
+            None => return Err(JoshutoError::new(format!("Register \"{} is empty", name))),
+        };
+
+    let destination = context.tabs[context.curr_tab_index].curr_path.clone();
+    let policy = conflict.unwrap_or(context.config_t.paste_conflict);
+    let items = conflict::plan_paste(&register.paths, &destination,
+            operation.unwrap_or(register.operation), policy,
+            context.config_t.rename_pattern.as_str(), PasteFiles::ask_conflict);
+
+    let curr_tab = &mut context.tabs[context.curr_tab_index];
+    curr_tab.refresh(&context.views, &context.config_t,
+        &context.username, &context.hostname);
+    let msg = match items {
+            Some(ref s) if s.is_empty() => "Nothing to paste",
+            Some(s) => return Ok(Some((register, s))),
+            None => "Paste cancelled",
+        };
+    ui::wprint_msg(&context.views.bot_win, msg);
+    ncurses::doupdate();
+    Ok(None)
 }
 
 #[derive(Clone, Debug)]
@@ -47,10 +109,17 @@ pub struct CopyOptions {
 }
 
 #[derive(Clone, Debug)]
-pub struct CutFiles;
+pub struct CutFiles {
+    register: Option<char>,
+}
 
 impl CutFiles {
-    pub fn new() -> Self { CutFiles }
+    pub fn new(register: Option<char>) -> Self
+    {
+        CutFiles {
+            register,
+        }
+    }
     pub const fn command() -> &'static str { "cut_files" }
 }
 
@@ -59,27 +128,31 @@ impl JoshutoCommand for CutFiles {}
 impl std::fmt::Display for CutFiles {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
     {
-        f.write_str(Self::command())
+        f.write_str(Self::command())?;
+        fmt_register(f, self.register)
     }
 }
 
 impl JoshutoRunnable for CutFiles {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
-        let curr_tab = &context.tabs[context.curr_tab_index];
-        if let Some(s) = curr_tab.curr_list.as_ref() {
-            if repopulated_selected_files(s) {
-                set_file_op(FileOp::Cut);
-            }
-        }
+        yank_selected(self.register, FileOp::Cut, context);
+        Ok(())
     }
 }
 
 #[derive(Clone, Debug)]
-pub struct CopyFiles;
+pub struct CopyFiles {
+    register: Option<char>,
+}
 
 impl CopyFiles {
-    pub fn new() -> Self { CopyFiles }
+    pub fn new(register: Option<char>) -> Self
+    {
+        CopyFiles {
+            register,
+        }
+    }
     pub const fn command() -> &'static str { "copy_files" }
 }
 
@@ -88,125 +161,196 @@ impl JoshutoCommand for CopyFiles {}
 impl std::fmt::Display for CopyFiles {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
     {
-        f.write_str(Self::command())
+        f.write_str(Self::command())?;
+        fmt_register(f, self.register)
     }
 }
 
 impl JoshutoRunnable for CopyFiles {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
-        let curr_tab = &context.tabs[context.curr_tab_index];
-        if let Some(s) = curr_tab.curr_list.as_ref() {
-            if repopulated_selected_files(s) {
-                set_file_op(FileOp::Copy);
-            }
-        }
+        yank_selected(self.register, FileOp::Copy, context);
+        Ok(())
     }
 }
 
 pub struct PasteFiles {
-    options: fs_extra::dir::CopyOptions,
+    /* overrides paste_conflict in joshuto.toml */
+    conflict: Option<ConflictPolicy>,
+    register: Option<char>,
 }
 
 impl PasteFiles {
-    pub fn new(options: fs_extra::dir::CopyOptions) -> Self
+    pub fn new(conflict: Option<ConflictPolicy>, register: Option<char>) -> Self
     {
         PasteFiles {
-            options,
+            conflict,
+            register,
         }
     }
     pub const fn command() -> &'static str { "paste_files" }
 
-    fn cut(&self, destination: &path::PathBuf)
-            -> (sync::mpsc::Receiver<command::ProgressInfo>, thread::JoinHandle<i32>)
+    /* size, modification time and, for files of the same size, whether they match */
+    fn compare_lines(source: &path::Path, destination: &path::Path) -> Vec<String>
     {
-        let (tx, rx) = sync::mpsc::channel();
-
-        let mut destination = destination.clone();
-        let options = self.options.clone();
-
-        let child = thread::spawn(move || {
-            let mut paths = selected_files.lock().unwrap();
-
-            let mut progress_info = ProgressInfo {
-                    bytes_finished: 1,
-                    total_bytes: paths.len() as u64 + 1,
-                };
-
-            for path in (*paths).iter() {
-                let mut file_name = path.file_name().unwrap().to_os_string();
-
-                while path::Path::new(&file_name).exists() {
-                    file_name.push("_0");
-                }
-
-                destination.push(file_name);
-                if options.skip_exist && destination.exists() {
-                    continue;
-                }
-
-                match std::fs::rename(&path, &destination) {
-                    Ok(_) => {
-                        destination.pop();
-                    },
-                    Err(_) => {
-                        if let Ok(metadata) = std::fs::symlink_metadata(path) {
-                            if metadata.is_dir() {
-                                destination.pop();
-                                match fs_extra::dir::move_dir(&path, &destination, &options) {
-                                    Ok(_) => {},
-                                    Err(e) => eprintln!("dir: {}", e),
-                                }
-                            } else {
-                                if let Ok(_) = std::fs::copy(&path, &destination) {
-                                    std::fs::remove_file(&path).unwrap();
-                                }
-                                destination.pop();
-                            }
+        let describe = |path: &path::Path| -> String {
+            match fs::symlink_metadata(path) {
+                Ok(metadata) => {
+                    let kind = if metadata.is_dir() {
+                            String::from("directory")
                         } else {
-                            destination.pop();
-                        }
+                            ui::file_size_to_string_detailed(metadata.len() as f64)
+                        };
+                    match metadata.modified() {
+                        Ok(s) => format!("{}  {}", kind, ui::file_mtime_to_string(s)),
+                        Err(_) => kind,
                     }
-                }
-
-                progress_info.bytes_finished = progress_info.bytes_finished + 1;
-                tx.send(progress_info.clone()).unwrap();
+                },
+                Err(e) => e.to_string(),
             }
+        };
+        let mut lines = vec![
+                format!("  source:       {}", describe(source)),
+                format!("  destination:  {}", describe(destination)),
+            ];
+        if let (Ok(s), Ok(d)) = (fs::metadata(source), fs::metadata(destination)) {
+            if s.is_file() && d.is_file() && s.len() == d.len() {
+                let same = match transfer::files_differ(source, destination) {
+                        Ok(s) => !s,
+                        Err(_) => false,
+                    };
+                lines.push(String::from(if same {
+                        "  contents are identical"
+                    } else {
+                        "  contents differ"
+                    }));
+            }
+        }
+        lines
+    }
 
-            paths.clear();
-            0
-        });
-
-        (rx, child)
+    /* ask what to do about destination being taken.
+     * None cancels the paste, true applies the answer to the remaining conflicts */
+    fn ask_conflict(source: &path::Path, destination: &path::Path)
+            -> Option<(ConflictPolicy, bool)>
+    {
+        const KEYS: &str = "  o overwrite, s skip, r rename, c compare, O S R for all, Escape cancels";
+
+        let mut lines = vec![format!("  {} already exists", destination.to_string_lossy())];
+        loop {
+            let mut display_vec = lines.clone();
+            display_vec.push(String::from(KEYS));
+
+            let ch = {
+                let (term_rows, term_cols) = ui::getmaxyx();
+                let rows = display_vec.len() as i32 + 1;
+                let win = window::JoshutoPanel::new(rows, term_cols,
+                        ((term_rows - rows - 1).max(0) as usize, 0));
+                ncurses::keypad(win.win, true);
+                win.move_to_top();
+                ui::display_options(&win, &display_vec);
+                ncurses::doupdate();
+                match ncurses::wget_wch(win.win) {
+                    Some(s) => keymap::wch_to_key(s),
+                    None => keymap::ESCAPE,
+                }
+            };
+            ncurses::doupdate();
+
+            let answer = match std::char::from_u32(ch as u32) {
+                    Some('o') => (ConflictPolicy::Overwrite, false),
+                    Some('s') => (ConflictPolicy::Skip, false),
+                    Some('r') => (ConflictPolicy::Rename, false),
+                    Some('O') => (ConflictPolicy::Overwrite, true),
+                    Some('S') => (ConflictPolicy::Skip, true),
+                    Some('R') => (ConflictPolicy::Rename, true),
+                    Some('c') => {
+                        lines.truncate(1);
+                        lines.extend(Self::compare_lines(source, destination));
+                        continue;
+                    },
+                    _ if ch == keymap::ESCAPE || ch == 'q' as i32 => return None,
+                    _ => continue,
+                };
+            return Some(answer);
+        }
     }
 
-    fn copy(&self, destination: &path::PathBuf)
-            -> (sync::mpsc::Receiver<command::ProgressInfo>, thread::JoinHandle<i32>)
+    fn total_size<'a, I>(items: I) -> u64
+        where I: IntoIterator<Item = &'a PasteItem>
     {
-        let (tx, rx) = sync::mpsc::channel();
+        items.into_iter()
+            .map(|item| fs_extra::dir::get_size(&item.source).unwrap_or(0))
+            .sum()
+    }
 
-        let destination = destination.clone();
-        let options = self.options.clone();
+    fn cut(items: Vec<PasteItem>, verify: MoveVerify, handle: JobHandle) -> JobResult
+    {
+        let mut report = JobReport::new();
+        /* only moves to another filesystem copy any bytes,
+         * a rename reports its progress as the item done */
+        let copied = items.iter()
+                .filter(|item| !transfer::same_filesystem(&item.source, &item.destination));
+        let mut progress_info = ProgressInfo {
+                bytes_finished: 0,
+                total_bytes: Self::total_size(copied),
+            };
+        handle.progress(progress_info.clone());
+        if let Some(s) = items.first().and_then(|item| item.destination.parent()) {
+            transfer::clean_stale_temps(s);
+        }
 
-        let child = thread::spawn(move || {
-            let files = selected_files.lock().unwrap();
+        for item in &items {
+            if !handle.check() {
+                break;
+            }
+            /* a merge into an existing directory can't be moved back */
+            let merge = item.overwrite && item.source.is_dir() && item.destination.is_dir();
+            /* nor can a move to another filesystem, see journal::check_same_fs */
+            let renamed = transfer::same_filesystem(&item.source, &item.destination);
+            let failed = report.errors.len();
+            let finished = transfer::move_item(&item.source, &item.destination, item.overwrite,
+                    verify, &mut |bytes| {
+                        progress_info.bytes_finished += bytes;
+                        handle.progress(progress_info.clone())
+                    }, &mut report);
+            /* a move stopped part way leaves source where it was */
+            let moved = report.errors.len() == failed && !merge && renamed
+                    && !conflict::path_exists(&item.source)
+                    && conflict::path_exists(&item.destination);
+            if moved {
+                handle.record(FileAction::new_move(item.source.clone(),
+                        item.destination.clone()));
+            }
+            if !finished {
+                break;
+            }
+        }
+        Ok(report)
+    }
 
-            let handle = |process_info: fs_extra::TransitProcess| {
-                let progress_info = ProgressInfo {
-                        bytes_finished: process_info.copied_bytes,
-                        total_bytes: process_info.total_bytes,
-                    };
-                tx.send(progress_info).unwrap();
-                fs_extra::dir::TransitProcessResult::ContinueOrAbort
+    fn copy(items: Vec<PasteItem>, handle: JobHandle) -> JobResult
+    {
+        let mut report = JobReport::new();
+        let mut progress_info = ProgressInfo {
+                bytes_finished: 0,
+                total_bytes: Self::total_size(&items),
             };
+        handle.progress(progress_info.clone());
 
-            match fs_extra::copy_items_with_progress(&files, &destination, &options, handle) {
-                Ok(_) => {},
-                Err(_) => {},
+        for item in &items {
+            if !handle.check() {
+                break;
+            }
+            if !transfer::copy_item(&item.source, &item.destination, item.overwrite, false,
+                    &mut |bytes| {
+                        progress_info.bytes_finished += bytes;
+                        handle.progress(progress_info.clone())
+                    }, &mut report) {
+                break;
             }
-            0
-        });
-        (rx, child)
+        }
+        Ok(report)
     }
 }
 
@@ -215,7 +359,9 @@ impl JoshutoCommand for PasteFiles {}
 impl std::fmt::Display for PasteFiles {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
     {
-        write!(f, "{} overwrite={}", Self::command(), self.options.overwrite)
+        f.write_str(Self::command())?;
+        fmt_conflict(f, self.conflict)?;
+        fmt_register(f, self.register)
     }
 }
 
@@ -227,21 +373,168 @@ impl std::fmt::Debug for PasteFiles {
 }
 
 impl JoshutoRunnable for PasteFiles {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        let name = register_name(self.register, context);
+        let (register, items) = match plan_register_paste(context, name, self.conflict, None)? {
+                Some(s) => s,
+                None => return Ok(()),
+            };
+        context.registers.take(name);
+
+        let destination = context.tabs[context.curr_tab_index].curr_path.clone();
+        let description = format!("{} {} file(s) to {}", register.operation.as_str(),
+                items.len(), destination.to_string_lossy());
+        let id = match register.operation {
+                FileOp::Copy => {
+                    context.jobs.spawn(description.clone(), ProgressUnit::Bytes, 0,
+                        move |handle| Self::copy(items, handle))
+                },
+                FileOp::Cut => {
+                    let verify = context.config_t.verify_move;
+                    context.jobs.spawn(description.clone(), ProgressUnit::Bytes, 0,
+                        move |handle| Self::cut(items, verify, handle))
+                },
+            };
+
+        ui::wprint_msg(&context.views.bot_win,
+                format!("Job {} started: {}", id, description).as_str());
+        ncurses::doupdate();
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LinkKind {
+    Symlink,
+    /* symlinks pointing at their target relative to the link's directory */
+    RelativeSymlink,
+    Hardlink,
+}
+
+/* links to the files in a register instead of copies of them */
+#[derive(Clone, Debug)]
+pub struct PasteLink {
+    kind: LinkKind,
+    conflict: Option<ConflictPolicy>,
+    register: Option<char>,
+}
+
+impl PasteLink {
+    pub fn new(kind: LinkKind, conflict: Option<ConflictPolicy>, register: Option<char>) -> Self
+    {
+        PasteLink {
+            kind,
+            conflict,
+            register,
+        }
+    }
+
+    pub fn command(&self) -> &'static str
+    {
+        match self.kind {
+            LinkKind::Symlink | LinkKind::RelativeSymlink => "paste_symlink",
+            LinkKind::Hardlink => "paste_hardlink",
+        }
+    }
+
+    /* source as seen from destination's directory, both with symlinked
+     * directories resolved so the link works wherever it was made from */
+    fn relative_target(source: &path::Path, destination: &path::Path) -> io::Result<path::PathBuf>
     {
-        let file_operation = fileop.lock().unwrap();
+        let real_parent = |path: &path::Path| -> io::Result<path::PathBuf> {
+            match path.parent() {
+                Some(s) => s.canonicalize(),
+                None => Ok(path.to_path_buf()),
+            }
+        };
+        let mut source_real = real_parent(source)?;
+        if let Some(s) = source.file_name() {
+            source_real.push(s);
+        }
+        Ok(relative_path(&source_real, &real_parent(destination)?))
+    }
+
+    fn link_item(item: &PasteItem, kind: LinkKind) -> io::Result<()>
+    {
+        if item.overwrite {
+            if let Ok(metadata) = fs::symlink_metadata(&item.destination) {
+                if metadata.is_dir() {
+                    fs::remove_dir_all(&item.destination)?;
+                } else {
+                    fs::remove_file(&item.destination)?;
+                }
+            }
+        }
+        match kind {
+            LinkKind::Symlink => unix::fs::symlink(&item.source, &item.destination),
+            LinkKind::RelativeSymlink => {
+                let target = Self::relative_target(&item.source, &item.destination)?;
+                unix::fs::symlink(target, &item.destination)
+            },
+            LinkKind::Hardlink => fs::hard_link(&item.source, &item.destination),
+        }
+    }
+
+    fn link(items: Vec<PasteItem>, kind: LinkKind, handle: JobHandle) -> JobResult
+    {
+        let mut report = JobReport::new();
+        let mut progress_info = ProgressInfo {
+                bytes_finished: 0,
+                total_bytes: items.len() as u64,
+            };
+        for item in &items {
+            if !handle.check() {
+                break;
+            }
+            report.add(&item.source, Self::link_item(item, kind));
+            progress_info.bytes_finished += 1;
+            handle.progress(progress_info.clone());
+        }
+        Ok(report)
+    }
+}
 
-        let curr_tab = &mut context.tabs[context.curr_tab_index];
-        let cprocess = match *file_operation {
-                FileOp::Copy => self.copy(&curr_tab.curr_path),
-                FileOp::Cut => self.cut(&curr_tab.curr_path),
+impl JoshutoCommand for PasteLink {}
+
+impl std::fmt::Display for PasteLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        f.write_str(self.command())?;
+        if self.kind == LinkKind::RelativeSymlink {
+            f.write_str(" relative=true")?;
+        }
+        fmt_conflict(f, self.conflict)?;
+        fmt_register(f, self.register)
+    }
+}
+
+impl JoshutoRunnable for PasteLink {
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        /* the register is left as it is, links don't move anything */
+        let name = register_name(self.register, context);
+        let (_, items) = match plan_register_paste(context, name, self.conflict,
+                Some(FileOp::Copy))? {
+                Some(s) => s,
+                None => return Ok(()),
             };
-        context.threads.push(cprocess);
 
-        curr_tab.reload_contents(&context.config_t.sort_type);
-        curr_tab.refresh(&context.views, &context.config_t,
-            &context.username, &context.hostname);
-        ncurses::timeout(0);
+        let destination = context.tabs[context.curr_tab_index].curr_path.clone();
+        let verb = match self.kind {
+                LinkKind::Symlink | LinkKind::RelativeSymlink => "symlink",
+                LinkKind::Hardlink => "hardlink",
+            };
+        let description = format!("{} {} file(s) to {}", verb, items.len(),
+                destination.to_string_lossy());
+        let kind = self.kind;
+        let total = items.len() as u64;
+        let id = context.jobs.spawn(description.clone(), ProgressUnit::Files, total,
+                move |handle| Self::link(items, kind, handle));
+
+        ui::wprint_msg(&context.views.bot_win,
+                format!("Job {} started: {}", id, description).as_str());
         ncurses::doupdate();
+        Ok(())
     }
 }
@@ -0,0 +1,54 @@
+extern crate ncurses;
+extern crate unicode_width;
+
+use std;
+
+use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
+use joshuto::command::JoshutoRunnable;
+use joshuto::context::JoshutoContext;
+use joshuto::pager;
+
+use self::unicode_width::UnicodeWidthStr;
+
+#[derive(Clone, Debug)]
+pub struct Help;
+
+impl Help {
+    pub fn new() -> Self { Help }
+    pub const fn command() -> &'static str { "help" }
+
+    /* one line per keybinding, commands lined up in a column */
+    pub fn help_lines(bindings: &[(String, String)]) -> Vec<String>
+    {
+        let keys_width = bindings.iter()
+                .map(|(keys, _)| keys.width())
+                .max()
+                .unwrap_or(0);
+        bindings.iter()
+            .map(|(keys, command)| {
+                let padding = keys_width - keys.width();
+                format!("  {}{}  {}", keys, " ".repeat(padding), command)
+            })
+            .collect()
+    }
+}
+
+impl JoshutoCommand for Help {}
+
+impl std::fmt::Display for Help {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        f.write_str(Self::command())
+    }
+}
+
+impl JoshutoRunnable for Help {
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        let lines = Self::help_lines(&context.keymap_t.bindings());
+        pager::show_pager("Keybindings", &lines);
+        ncurses::doupdate();
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+extern crate ncurses;
+
+use std;
+use std::time;
+
+use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
+use joshuto::command::JoshutoRunnable;
+use joshuto::config::keymap;
+use joshuto::context::JoshutoContext;
+use joshuto::job;
+use joshuto::job::JobStatus;
+use joshuto::job::JoshutoJob;
+use joshuto::job::ProgressUnit;
+use joshuto::pager;
+use joshuto::ui;
+use joshuto::window;
+
+#[derive(Clone, Debug)]
+pub struct ShowJobs;
+
+impl ShowJobs {
+    pub fn new() -> Self { ShowJobs }
+    pub const fn command() -> &'static str { "jobs" }
+
+    fn duration_to_string(duration: time::Duration) -> String
+    {
+        let secs = duration.as_secs();
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    }
+
+    /* id, status, progress, throughput, time left and description */
+    pub fn job_line(job: &JoshutoJob) -> String
+    {
+        let throughput = match job.unit {
+                ProgressUnit::Bytes => {
+                    format!("{}/s", ui::file_size_to_string_detailed(job.throughput()).trim())
+                },
+                ProgressUnit::Files => format!("{:.1} files/s", job.throughput()),
+            };
+        let eta = match job.eta() {
+                Some(s) => Self::duration_to_string(s),
+                None => String::from("-"),
+            };
+        let mut line = format!("{:>3}  {:<9} {:>3}%  {:>12}  ETA {:>8}  {}",
+                job.id, job.status.as_str(), (job.percentage() * 100.0) as u32,
+                throughput, eta, job.description);
+        if let JobStatus::Failed(e) = &job.status {
+            line.push_str(": ");
+            line.push_str(e.as_str());
+        }
+        if let Some(s) = job.report.summary() {
+            line.push_str(": ");
+            line.push_str(s.as_str());
+        }
+        line
+    }
+
+    /* full screen list of jobs, redrawn as they make progress */
+    fn show_jobs(context: &mut JoshutoContext)
+    {
+        const FOOTER: &str = "p pause/resume, c cancel, q close";
+
+        let (term_rows, term_cols) = ui::getmaxyx();
+        let win = window::JoshutoPanel::new(term_rows, term_cols, (0, 0));
+        ncurses::keypad(win.win, true);
+        ncurses::wtimeout(win.win, job::JOB_POLL_MILLIS);
+        win.move_to_top();
+
+        let mut index: usize = 0;
+        let mut status_msg: Option<String> = None;
+        loop {
+            context.jobs.poll();
+            let ids: Vec<usize> = context.jobs.iter().map(|job| job.id).collect();
+            index = index.min(ids.len().saturating_sub(1));
+
+            ncurses::werase(win.win);
+            ncurses::wattron(win.win, ncurses::A_BOLD());
+            ncurses::mvwaddnstr(win.win, 0, 0, "Jobs", term_cols);
+            ncurses::wattroff(win.win, ncurses::A_BOLD());
+            for (i, job) in context.jobs.iter().enumerate().take((term_rows - 2).max(0) as usize) {
+                if i == index {
+                    ncurses::wattron(win.win, ncurses::A_STANDOUT());
+                }
+                ncurses::mvwaddnstr(win.win, i as i32 + 1, 0,
+                        Self::job_line(job).as_str(), term_cols);
+                if i == index {
+                    ncurses::wattroff(win.win, ncurses::A_STANDOUT());
+                }
+            }
+            if ids.is_empty() {
+                ncurses::mvwaddnstr(win.win, 1, 0, "No jobs", term_cols);
+            }
+            let footer = match status_msg.as_ref() {
+                    Some(s) => s.as_str(),
+                    None => FOOTER,
+                };
+            ncurses::mvwaddnstr(win.win, term_rows - 1, 0, footer, term_cols);
+            win.queue_for_refresh();
+            ncurses::doupdate();
+
+            let ch = match ncurses::wget_wch(win.win) {
+                    Some(s) => keymap::wch_to_key(s),
+                    None => continue,
+                };
+            status_msg = None;
+            if ch == keymap::ESCAPE || ch == 'q' as i32 {
+                break;
+            } else if ch == keymap::keycode(ncurses::KEY_UP) || ch == 'k' as i32 {
+                index = index.saturating_sub(1);
+            } else if ch == keymap::keycode(ncurses::KEY_DOWN) || ch == 'j' as i32 {
+                index += 1;
+            } else if let Some(id) = ids.get(index) {
+                let result = if ch == 'p' as i32 {
+                        context.jobs.pause(*id).or_else(|_| context.jobs.resume(*id))
+                    } else if ch == 'c' as i32 {
+                        context.jobs.cancel(*id)
+                    } else {
+                        Ok(())
+                    };
+                if let Err(e) = result {
+                    status_msg = Some(e);
+                }
+            }
+        }
+    }
+}
+
+impl JoshutoCommand for ShowJobs {}
+
+impl std::fmt::Display for ShowJobs {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        f.write_str(Self::command())
+    }
+}
+
+impl JoshutoRunnable for ShowJobs {
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        Self::show_jobs(context);
+        ncurses::doupdate();
+        Ok(())
+    }
+}
+
+/* the files finished jobs failed on, most recent job first */
+#[derive(Clone, Debug)]
+pub struct JobErrors;
+
+impl JobErrors {
+    pub fn new() -> Self { JobErrors }
+    pub const fn command() -> &'static str { "job_errors" }
+
+    pub fn error_lines(context: &JoshutoContext) -> Vec<String>
+    {
+        let mut lines: Vec<String> = Vec::new();
+        for job in context.jobs.iter().rev() {
+            if let Some(summary) = job.report.summary() {
+                lines.push(format!("Job {}: {}: {}", job.id, job.description, summary));
+                for file_error in &job.report.errors {
+                    lines.push(format!("    {}: {}", file_error.path.to_string_lossy(),
+                            file_error.error));
+                }
+            }
+        }
+        lines
+    }
+}
+
+impl JoshutoCommand for JobErrors {}
+
+impl std::fmt::Display for JobErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        f.write_str(Self::command())
+    }
+}
+
+impl JoshutoRunnable for JobErrors {
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        let lines = Self::error_lines(context);
+        if lines.is_empty() {
+            ui::wprint_msg(&context.views.bot_win, "No failed files");
+        } else {
+            pager::show_pager("Job errors", &lines);
+        }
+        ncurses::doupdate();
+        Ok(())
+    }
+}
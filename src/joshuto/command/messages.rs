@@ -0,0 +1,40 @@
+extern crate ncurses;
+
+use std;
+
+use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
+use joshuto::command::JoshutoRunnable;
+use joshuto::context::JoshutoContext;
+use joshuto::pager;
+use joshuto::ui;
+
+#[derive(Clone, Debug)]
+pub struct Messages;
+
+impl Messages {
+    pub fn new() -> Self { Messages }
+    pub const fn command() -> &'static str { "messages" }
+}
+
+impl JoshutoCommand for Messages {}
+
+impl std::fmt::Display for Messages {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        f.write_str(Self::command())
+    }
+}
+
+impl JoshutoRunnable for Messages {
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        if context.messages.is_empty() {
+            ui::wprint_msg(&context.views.bot_win, "No messages");
+        } else {
+            pager::show_pager("Messages", &context.messages);
+        }
+        ncurses::doupdate();
+        Ok(())
+    }
+}
@@ -5,8 +5,13 @@ use std::path;
 
 use joshuto::command::ReloadDirList;
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
+use joshuto::completion::CompletionKind;
+use joshuto::completion::JoshutoCompleter;
 use joshuto::context::JoshutoContext;
+use joshuto::journal::FileAction;
+use joshuto::journal::JournalEntry;
 use joshuto::textfield::JoshutoTextField;
 use joshuto::ui;
 
@@ -28,7 +33,7 @@ impl std::fmt::Display for NewDirectory {
 }
 
 impl JoshutoRunnable for NewDirectory {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         let (term_rows, term_cols) = ui::getmaxyx();
         const PROMPT: &'static str = ":mkdir ";
@@ -36,23 +41,34 @@ impl JoshutoRunnable for NewDirectory {
         let user_input: Option<String>;
 
         {
-            let textfield = JoshutoTextField::new(1, term_cols, (term_rows as usize - 1, 0), PROMPT.to_string());
+            let mut textfield = JoshutoTextField::new(1, term_cols, (term_rows as usize - 1, 0), PROMPT.to_string());
+            let curr_path = context.tabs[context.curr_tab_index].curr_path.clone();
+            textfield.set_completer(JoshutoCompleter::new(CompletionKind::FileName, curr_path));
+            textfield.set_history(context.input_history.get(Self::command()));
             user_input = textfield.readline_with_initial("", "");
         }
 
         if let Some(user_input) = user_input {
-            let path = path::PathBuf::from(user_input);
-
-            match std::fs::create_dir_all(&path) {
-                Ok(_) => {
-                    ReloadDirList::reload(context);
-                },
-                Err(e) => {
-                    ui::wprint_err(&context.views.bot_win, e.to_string().as_str());
-                },
+            context.input_history.push(Self::command(), user_input.as_str());
+            let path = context.tabs[context.curr_tab_index].curr_path.join(user_input);
+
+            /* the directories create_dir_all is about to make, outermost first */
+            let mut created: Vec<path::PathBuf> = path.ancestors()
+                    .take_while(|s| !s.exists())
+                    .map(|s| s.to_path_buf())
+                    .collect();
+            created.reverse();
+
+            std::fs::create_dir_all(&path)?;
+            let description = format!("mkdir {}", path.to_string_lossy());
+            if !created.is_empty() {
+                let action = FileAction::Mkdir(created);
+                context.journal.push(JournalEntry::new(description, vec![action]));
             }
+            ReloadDirList::reload(context);
         }
 
         ncurses::doupdate();
+        Ok(())
     }
 }
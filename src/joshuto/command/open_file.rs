@@ -4,12 +4,19 @@ extern crate open;
 
 use std;
 use std::env;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
 use std::path;
+use std::process;
 
 use joshuto::context::JoshutoContext;
 use joshuto::command;
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
+use joshuto::completion::CompletionKind;
+use joshuto::completion::JoshutoCompleter;
 use joshuto::config::mimetype;
 use joshuto::preview;
 use joshuto::textfield::JoshutoTextField;
@@ -26,7 +33,7 @@ impl OpenFile {
     pub fn new() -> Self { OpenFile }
     pub const fn command() -> &'static str { "open_file" }
 
-    pub fn get_options<'a>(path: &path::PathBuf)
+    pub fn get_options<'a>(mimetype: &'a mimetype::JoshutoMimetype, path: &path::PathBuf)
             -> Vec<&'a mimetype::JoshutoMimetypeEntry>
     {
         let mut mimetype_options: Vec<&mimetype::JoshutoMimetypeEntry> = Vec::new();
@@ -34,7 +41,7 @@ impl OpenFile {
         match path.extension() {
             Some(file_ext) => {
                 if let Some(file_ext) = file_ext.to_str() {
-                    match mimetype_t.extensions.get(file_ext) {
+                    match mimetype.extensions.get(file_ext) {
                         Some(s) => {
                             for option in s {
                                 mimetype_options.push(&option);
@@ -49,7 +56,7 @@ impl OpenFile {
         let detective = mime_detective::MimeDetective::new().unwrap();
         match detective.detect_filepath(path) {
             Ok(mime_type) => {
-                match mimetype_t.mimetypes.get(mime_type.type_().as_str()) {
+                match mimetype.mimetypes.get(mime_type.type_().as_str()) {
                     Some(s) => {
                         for option in s {
                             mimetype_options.push(&option);
@@ -64,15 +71,12 @@ impl OpenFile {
     }
 
     fn into_directory(path: &path::PathBuf, context: &mut JoshutoContext)
+            -> Result<(), JoshutoError>
     {
         let curr_tab = &mut context.tabs[context.curr_tab_index];
 
-        match env::set_current_dir(path) {
-            Ok(_) => {},
-            Err(e) => {
-                ui::wprint_err(&context.views.bot_win, format!("{}: {:?}", e, path).as_str());
-                return;
-            }
+        if let Err(e) = env::set_current_dir(path) {
+            return Err(JoshutoError::new(format!("{}: {:?}", e, path)));
         }
 
         {
@@ -93,28 +97,64 @@ impl OpenFile {
 
         /* update curr_path */
         match path.strip_prefix(curr_tab.curr_path.as_path()) {
-            Ok(s) => curr_tab.curr_path.push(s),
-            Err(e) => {
-                ui::wprint_err(&context.views.bot_win, e.to_string().as_str());
-                return;
+            Ok(s) => {
+                curr_tab.curr_path.push(s);
+                Ok(())
+            },
+            Err(e) => Err(JoshutoError::new(e.to_string())),
+        }
+    }
+
+    /* one path per line, or NUL separated */
+    fn write_choosefiles(file: &path::Path, paths: &[path::PathBuf], null: bool)
+            -> io::Result<()>
+    {
+        let separator: u8 = if null { b'\0' } else { b'\n' };
+        let mut contents: Vec<u8> = Vec::new();
+        for path in paths {
+            contents.extend_from_slice(path.as_os_str().as_bytes());
+            contents.push(separator);
+        }
+        fs::write(file, contents)
+    }
+
+    /* exits joshuto, unless writing the output fails */
+    fn choose_files(paths: &[path::PathBuf], context: &mut JoshutoContext)
+            -> Result<(), JoshutoError>
+    {
+        if !command::Quit::stop_jobs(context) {
+            return Err(JoshutoError::new(String::from("choosefiles: jobs still running")));
+        }
+        if let Some(s) = context.choosefiles.as_ref() {
+            if let Err(e) = Self::write_choosefiles(s, paths, context.choose_null) {
+                return Err(JoshutoError::new(format!("choosefiles: {}", e)));
             }
         }
+        if let Err(e) = command::Quit::write_choosedir(context) {
+            return Err(JoshutoError::new(format!("choosedir: {}", e)));
+        }
+        ui::end_ncurses();
+        process::exit(0);
     }
 
-    fn into_file(paths: &Vec<path::PathBuf>)
+    fn into_file(paths: &Vec<path::PathBuf>) -> Result<(), JoshutoError>
     {
-        let mimetype_options = Self::get_options(&paths[0]);
+        let mimetype = mimetype_t.read().unwrap();
+        let mimetype_options = Self::get_options(&mimetype, &paths[0]);
 
         ncurses::savetty();
         ncurses::endwin();
-        if mimetype_options.len() > 0 {
-            unix::open_with_entry(paths, &mimetype_options[0]);
-        } else {
-            open::that(&paths[0]).unwrap();
-        }
+        let result = if mimetype_options.len() > 0 {
+                unix::open_with_entry(paths, &mimetype_options[0]);
+                Ok(())
+            } else {
+                open::that(&paths[0]).map(|_| ())
+            };
         ncurses::resetty();
         ncurses::refresh();
         ncurses::doupdate();
+        result?;
+        Ok(())
     }
 }
 
@@ -128,7 +168,7 @@ impl std::fmt::Display for OpenFile {
 }
 
 impl JoshutoRunnable for OpenFile {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         let mut path: Option<path::PathBuf> = None;
         if let Some(curr_list) = context.tabs[context.curr_tab_index].curr_list.as_ref() {
@@ -139,7 +179,7 @@ impl JoshutoRunnable for OpenFile {
             }
         }
         if let Some(path) = path {
-            Self::into_directory(&path, context);
+            Self::into_directory(&path, context)?;
             {
                 let curr_tab = &mut context.tabs[context.curr_tab_index];
                 curr_tab.refresh(&context.views, &context.config_t,
@@ -154,7 +194,10 @@ impl JoshutoRunnable for OpenFile {
                 };
             if let Some(paths) = paths {
                 if paths.len() > 0 {
-                    Self::into_file(&paths);
+                    if context.choosefiles.is_some() {
+                        Self::choose_files(&paths, context)?;
+                    }
+                    Self::into_file(&paths)?;
                 } else {
                     ui::wprint_msg(&context.views.bot_win, "No files selected: 0");
                 }
@@ -163,6 +206,7 @@ impl JoshutoRunnable for OpenFile {
             }
             ncurses::doupdate();
         }
+        Ok(())
     }
 }
 
@@ -173,11 +217,13 @@ impl OpenFileWith {
     pub fn new() -> Self { OpenFileWith }
     pub const fn command() -> &'static str { "open_file_with" }
 
-    pub fn open_with(paths: &Vec<path::PathBuf>)
+    pub fn open_with(paths: &Vec<path::PathBuf>, context: &mut JoshutoContext)
     {
         const PROMPT: &str = ":open_with ";
 
-        let mimetype_options: Vec<&mimetype::JoshutoMimetypeEntry> = OpenFile::get_options(&paths[0]);
+        let mimetype = mimetype_t.read().unwrap();
+        let mimetype_options: Vec<&mimetype::JoshutoMimetypeEntry> =
+                OpenFile::get_options(&mimetype, &paths[0]);
         let user_input: Option<String>;
         {
             let (term_rows, term_cols) = ui::getmaxyx();
@@ -197,7 +243,10 @@ impl OpenFileWith {
             ncurses::doupdate();
 
 
-            let textfield = JoshutoTextField::new(1, term_cols, (term_rows as usize - 1, 0), PROMPT.to_string());
+            let mut textfield = JoshutoTextField::new(1, term_cols, (term_rows as usize - 1, 0), PROMPT.to_string());
+            let curr_path = context.tabs[context.curr_tab_index].curr_path.clone();
+            textfield.set_completer(JoshutoCompleter::new(CompletionKind::Arguments, curr_path));
+            textfield.set_history(context.input_history.get(Self::command()));
             user_input = textfield.readline_with_initial("", "");
         }
         ncurses::doupdate();
@@ -206,6 +255,7 @@ impl OpenFileWith {
             if user_input.len() == 0 {
                 return;
             }
+            context.input_history.push(Self::command(), user_input.as_str());
             match user_input.parse::<usize>() {
                 Ok(s) => {
                     if s < mimetype_options.len() {
@@ -217,7 +267,8 @@ impl OpenFileWith {
                     }
                 }
                 Err(_) => {
-                    let args: Vec<String> = user_input.split_whitespace().map(|x| String::from(x)).collect();
+                    let args: Vec<String> = command::split_shell_style(user_input.as_str())
+                            .iter().map(|x| String::from(*x)).collect();
                     ncurses::savetty();
                     ncurses::endwin();
                     unix::open_with_args(&paths, &args);
@@ -239,12 +290,15 @@ impl std::fmt::Display for OpenFileWith {
 }
 
 impl JoshutoRunnable for OpenFileWith {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
-        if let Some(s) = context.tabs[context.curr_tab_index].curr_list.as_ref() {
-            if let Some(paths) = command::collect_selected_paths(s) {
-                Self::open_with(&paths);
-            }
+        let paths: Option<Vec<path::PathBuf>> = match context.tabs[context.curr_tab_index].curr_list.as_ref() {
+                Some(s) => command::collect_selected_paths(s),
+                None => None,
+            };
+        if let Some(paths) = paths {
+            Self::open_with(&paths, context);
         }
+        Ok(())
     }
 }
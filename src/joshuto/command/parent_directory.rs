@@ -4,6 +4,7 @@ use std;
 
 use joshuto::context::JoshutoContext;
 use joshuto::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
 use joshuto::preview;
 use joshuto::ui;
@@ -15,13 +16,13 @@ impl ParentDirectory {
     pub fn new() -> Self { ParentDirectory }
     pub const fn command() -> &'static str { "parent_directory" }
 
-    pub fn parent_directory(context: &mut JoshutoContext)
+    pub fn parent_directory(context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         if context.curr_tab_mut().curr_path.pop() == false {
-            return;
+            return Ok(());
         }
 
-        match std::env::set_current_dir(&context.curr_tab_ref().curr_path) {
+        let result = match std::env::set_current_dir(&context.curr_tab_ref().curr_path) {
             Ok(_) => {
                 {
                     let curr_tab = &mut context.tabs[context.curr_tab_index];
@@ -50,12 +51,12 @@ impl ParentDirectory {
                         &context.username, &context.hostname);
                 }
                 preview::preview_file(context);
+                Ok(())
             },
-            Err(e) => {
-                ui::wprint_err(&context.views.bot_win, e.to_string().as_str());
-            },
+            Err(e) => Err(JoshutoError::from(e)),
         };
         ncurses::doupdate();
+        result
     }
 }
 
@@ -69,8 +70,8 @@ impl std::fmt::Display for ParentDirectory {
 }
 
 impl JoshutoRunnable for ParentDirectory {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
-        Self::parent_directory(context);
+        Self::parent_directory(context)
     }
 }
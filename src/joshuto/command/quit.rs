@@ -1,8 +1,14 @@
+extern crate ncurses;
+
 use std;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
 use std::process;
 
 use joshuto::context::JoshutoContext;
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
 use joshuto::ui;
 
@@ -12,6 +18,41 @@ pub struct Quit;
 impl Quit {
     pub fn new() -> Self { Quit }
     pub const fn command() -> &'static str { "quit" }
+
+    /* for shell functions to cd into after joshuto exits */
+    pub fn write_choosedir(context: &JoshutoContext) -> io::Result<()>
+    {
+        if let Some(s) = context.choosedir.as_ref() {
+            let curr_path = &context.tabs[context.curr_tab_index].curr_path;
+            fs::write(s, curr_path.as_os_str().as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /* before exiting, running jobs are cancelled if the user agrees.
+     * false if they are to be left running instead */
+    pub fn stop_jobs(context: &mut JoshutoContext) -> bool
+    {
+        let running = context.jobs.iter().filter(|job| !job.status.is_done()).count();
+        if running == 0 {
+            return true;
+        }
+        let prompt = format!("{} job(s) running, cancel them and quit? (y/N)", running);
+        ui::wprint_msg(&context.views.bot_win, prompt.as_str());
+        ncurses::timeout(-1);
+        ncurses::doupdate();
+
+        if ncurses::getch() != 'y' as i32 {
+            let curr_tab = &context.tabs[context.curr_tab_index];
+            curr_tab.refresh_file_status(&context.views.bot_win);
+            ncurses::doupdate();
+            return false;
+        }
+        ui::wprint_msg(&context.views.bot_win, "Waiting for jobs to stop");
+        ncurses::doupdate();
+        context.jobs.cancel_all();
+        true
+    }
 }
 
 impl JoshutoCommand for Quit {}
@@ -24,8 +65,14 @@ impl std::fmt::Display for Quit {
 }
 
 impl JoshutoRunnable for Quit {
-    fn execute(&self, _: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
+        if !Self::stop_jobs(context) {
+            return Ok(());
+        }
+        if let Err(e) = Self::write_choosedir(context) {
+            return Err(JoshutoError::new(format!("choosedir: {}", e)));
+        }
         ui::end_ncurses();
         process::exit(0);
     }
@@ -0,0 +1,41 @@
+extern crate ncurses;
+
+use std;
+
+use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
+use joshuto::command::JoshutoRunnable;
+use joshuto::context::JoshutoContext;
+use joshuto::pager;
+use joshuto::ui;
+
+#[derive(Clone, Debug)]
+pub struct ShowRegisters;
+
+impl ShowRegisters {
+    pub fn new() -> Self { ShowRegisters }
+    pub const fn command() -> &'static str { "registers" }
+}
+
+impl JoshutoCommand for ShowRegisters {}
+
+impl std::fmt::Display for ShowRegisters {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        f.write_str(Self::command())
+    }
+}
+
+impl JoshutoRunnable for ShowRegisters {
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        let lines = context.registers.lines();
+        if lines.is_empty() {
+            ui::wprint_msg(&context.views.bot_win, "All registers are empty");
+        } else {
+            pager::show_pager("Registers", &lines);
+        }
+        ncurses::doupdate();
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+extern crate ncurses;
+
+use std;
+use std::rc::Rc;
+
+use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
+use joshuto::command::JoshutoRunnable;
+use joshuto::config;
+use joshuto::config::ConfigError;
+use joshuto::context::JoshutoContext;
+use joshuto::pager;
+use joshuto::preview;
+use joshuto::ui;
+use joshuto::window::JoshutoView;
+
+use joshuto::mimetype_t;
+use joshuto::theme_t;
+
+#[derive(Clone, Debug)]
+pub struct ReloadConfig;
+
+impl ReloadConfig {
+    pub fn new() -> Self { ReloadConfig }
+    pub const fn command() -> &'static str { "reload_config" }
+
+    /* all or nothing, a config with errors leaves the current one in place */
+    pub fn reload(context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        let mut errors: Vec<ConfigError> = Vec::new();
+        config::parse::check_config_dir(&mut errors);
+        let config_t = config::JoshutoConfig::load(&mut errors);
+        let keymap_t = config::JoshutoKeymap::load(&config_t.aliases, &mut errors);
+        let theme = config::JoshutoTheme::load(&mut errors);
+        let mimetype = config::JoshutoMimetype::load(&mut errors);
+
+        if !errors.is_empty() {
+            let lines: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            pager::show_pager("Config errors, keeping the current config", &lines);
+            return Err(JoshutoError::new(format!("{}: {} error(s), kept the current config",
+                    Self::command(), errors.len())));
+        }
+
+        *theme_t.write().unwrap() = theme;
+        *mimetype_t.write().unwrap() = mimetype;
+        ui::process_theme();
+
+        if config_t.column_ratio != context.config_t.column_ratio {
+            context.views = JoshutoView::new(config_t.column_ratio);
+        }
+        context.config_t = config_t;
+        context.keymap_t = Rc::new(keymap_t);
+        Ok(())
+    }
+}
+
+impl JoshutoCommand for ReloadConfig {}
+
+impl std::fmt::Display for ReloadConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        f.write_str(Self::command())
+    }
+}
+
+impl JoshutoRunnable for ReloadConfig {
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        let result = Self::reload(context);
+        {
+            let curr_tab = &mut context.tabs[context.curr_tab_index];
+            curr_tab.refresh(&context.views, &context.config_t,
+                &context.username, &context.hostname);
+        }
+        preview::preview_file(context);
+        ui::redraw_tab_view(&context.views.tab_win, context);
+        if result.is_ok() {
+            ui::wprint_msg(&context.views.bot_win, "Config reloaded");
+        }
+        ncurses::doupdate();
+        result
+    }
+}
@@ -3,6 +3,7 @@ extern crate ncurses;
 use std;
 
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
 use joshuto::context::JoshutoContext;
 use joshuto::preview;
@@ -33,10 +34,11 @@ impl std::fmt::Display for ReloadDirList {
 }
 
 impl JoshutoRunnable for ReloadDirList {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         Self::reload(context);
         preview::preview_file(context);
         ncurses::doupdate();
+        Ok(())
     }
 }
@@ -4,8 +4,13 @@ use std::path;
 use std::fs;
 
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
+use joshuto::completion::CompletionKind;
+use joshuto::completion::JoshutoCompleter;
 use joshuto::context::JoshutoContext;
+use joshuto::journal::FileAction;
+use joshuto::journal::JournalEntry;
 use joshuto::preview;
 use joshuto::textfield::JoshutoTextField;
 use joshuto::ui;
@@ -32,13 +37,17 @@ impl RenameFile {
     pub const fn command() -> &'static str { "rename_file" }
 
     pub fn rename_file(&self, path: &path::PathBuf, context: &mut JoshutoContext, start_str: String)
+            -> Result<(), JoshutoError>
     {
         const PROMPT: &str = ":rename_file ";
         let (term_rows, term_cols) = ui::getmaxyx();
         let user_input: Option<String>;
         {
-            let textfield = JoshutoTextField::new(1,
+            let mut textfield = JoshutoTextField::new(1,
                 term_cols, (term_rows as usize - 1, 0), PROMPT.to_string());
+            let curr_path = context.tabs[context.curr_tab_index].curr_path.clone();
+            textfield.set_completer(JoshutoCompleter::new(CompletionKind::FileName, curr_path));
+            textfield.set_history(context.input_history.get(Self::command()));
 
             user_input = match self.method {
                 RenameFileMethod::Append => {
@@ -58,29 +67,28 @@ impl RenameFile {
         }
 
         if let Some(s) = user_input {
+            context.input_history.push(Self::command(), s.as_str());
             let mut new_path = path.parent().unwrap().to_path_buf();
 
             new_path.push(s);
             if new_path.exists() {
-                ui::wprint_err(&context.views.bot_win, "Error: File with name exists");
-                return;
+                return Err(JoshutoError::new(String::from("Error: File with name exists")));
             }
-            match fs::rename(&path, &new_path) {
-                Ok(_) => {
-                    let curr_tab = &mut context.tabs[context.curr_tab_index];
-                    if let Some(ref mut s) = curr_tab.curr_list {
-                        s.update_contents(&context.config_t.sort_type).unwrap();
-                    }
-                    curr_tab.refresh_curr(&context.views.mid_win, context.config_t.scroll_offset);
-                },
-                Err(e) => {
-                    ui::wprint_err(&context.views.bot_win, e.to_string().as_str());
-                },
+            fs::rename(&path, &new_path)?;
+            let description = format!("rename {}", path.to_string_lossy());
+            let action = FileAction::new_move(path.clone(), new_path);
+            context.journal.push(JournalEntry::new(description, vec![action]));
+
+            let curr_tab = &mut context.tabs[context.curr_tab_index];
+            if let Some(ref mut s) = curr_tab.curr_list {
+                s.update_contents(&context.config_t.sort_type)?;
             }
+            curr_tab.refresh_curr(&context.views.mid_win, context.config_t.scroll_offset);
         } else {
             let curr_tab = &context.tabs[context.curr_tab_index];
             curr_tab.refresh_file_status(&context.views.bot_win);
         }
+        Ok(())
     }
 }
 
@@ -94,7 +102,7 @@ impl std::fmt::Display for RenameFile {
 }
 
 impl JoshutoRunnable for RenameFile {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         let mut path: Option<path::PathBuf> = None;
         let mut file_name: Option<String> = None;
@@ -108,10 +116,11 @@ impl JoshutoRunnable for RenameFile {
 
         if let Some(file_name) = file_name {
             if let Some(path) = path {
-                self.rename_file(&path, context, file_name);
+                self.rename_file(&path, context, file_name)?;
                 preview::preview_file(context);
                 ncurses::doupdate();
             }
         }
+        Ok(())
     }
 }
@@ -4,6 +4,7 @@ use std;
 
 use joshuto::command::CursorMove;
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
 use joshuto::context::JoshutoContext;
 use joshuto::textfield::JoshutoTextField;
@@ -27,14 +28,15 @@ impl std::fmt::Display for Search {
 }
 
 impl JoshutoRunnable for Search {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         const PROMPT: &str = ":search ";
         let (term_rows, term_cols) = ui::getmaxyx();
         let user_input: Option<String>;
         {
-            let textfield = JoshutoTextField::new(1,
+            let mut textfield = JoshutoTextField::new(1,
                 term_cols, (term_rows as usize - 1, 0), PROMPT.to_string());
+            textfield.set_history(context.input_history.get(Self::command()));
 
             user_input = textfield.readline_with_initial("", "");
         }
@@ -43,6 +45,7 @@ impl JoshutoRunnable for Search {
         let mut index: Option<i32> = None;
 
         if let Some(user_input) = user_input {
+            context.input_history.push(Self::command(), user_input.as_str());
             let user_input = user_input.to_lowercase();
 
             let curr_tab = &context.tabs[context.curr_tab_index];
@@ -64,5 +67,6 @@ impl JoshutoRunnable for Search {
             CursorMove::cursor_move(index, context);
         }
         ncurses::doupdate();
+        Ok(())
     }
 }
@@ -1,8 +1,11 @@
+extern crate ncurses;
+
 use std;
 
 use joshuto::context::JoshutoContext;
 use joshuto::command::CursorMove;
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
 
 #[derive(Debug, Clone)]
@@ -32,9 +35,18 @@ impl std::fmt::Display for SelectFiles {
 }
 
 impl JoshutoRunnable for SelectFiles {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
-        if self.toggle && !self.all {
+        if self.all {
+            if let Some(s) = context.tabs[context.curr_tab_index].curr_list.as_mut() {
+                for entry in s.contents.iter_mut() {
+                    entry.selected = !self.toggle || !entry.selected;
+                }
+            }
+            let curr_tab = &mut context.tabs[context.curr_tab_index];
+            curr_tab.refresh_curr(&context.views.mid_win, context.config_t.scroll_offset);
+            ncurses::doupdate();
+        } else if self.toggle {
             let mut selected = false;
 
             if let Some(s) = context.tabs[context.curr_tab_index].curr_list.as_mut() {
@@ -43,8 +55,26 @@ impl JoshutoRunnable for SelectFiles {
             }
             if selected {
                 let subcommand = CursorMove::new(1);
-                subcommand.execute(context);
+                subcommand.execute(context)?;
             }
         }
+        Ok(())
+    }
+    /* with a count, toggle that many entries starting from the cursor */
+    fn execute_count(&self, context: &mut JoshutoContext, count: usize)
+            -> Result<(), JoshutoError>
+    {
+        if self.all || !self.toggle {
+            return self.execute(context);
+        }
+        /* the cursor stops on the last entry, which mustn't be toggled again */
+        let left = match context.tabs[context.curr_tab_index].curr_list.as_ref() {
+            Some(s) => s.contents.len().saturating_sub(s.index.max(0) as usize),
+            None => 0,
+        };
+        for _ in 0..count.min(left) {
+            self.execute(context)?;
+        }
+        Ok(())
     }
 }
@@ -1,10 +1,15 @@
 extern crate ncurses;
 
 use std;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
 
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
 use joshuto::context::JoshutoContext;
+use joshuto::journal::FileAction;
+use joshuto::journal::JournalEntry;
 use joshuto::structs::JoshutoDirEntry;
 use joshuto::textfield::JoshutoTextField;
 use joshuto::ui;
@@ -71,15 +76,17 @@ impl std::fmt::Display for SetMode {
 }
 
 impl JoshutoRunnable for SetMode {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         let mut ok = false;
+        let mut old_mode: Option<(std::path::PathBuf, u32)> = None;
         {
             use std::os::unix::fs::PermissionsExt;
             let curr_tab = &mut context.tabs[context.curr_tab_index];
             if let Some(s) = curr_tab.curr_list.as_mut() {
                 if let Some(file) = s.get_curr_mut() {
                     let mode = file.metadata.permissions.mode();
+                    old_mode = Some((file.path.clone(), mode & 0o7777));
                     let mut mode_string = unix::stringify_mode(mode);
                     mode_string.remove(0);
 
@@ -88,9 +95,21 @@ impl JoshutoRunnable for SetMode {
             }
         }
         if ok {
+            if let Some((path, old_mode)) = old_mode {
+                if let Ok(metadata) = fs::symlink_metadata(&path) {
+                    let description = format!("set_mode {}", path.to_string_lossy());
+                    let action = FileAction::SetMode {
+                            path,
+                            old_mode,
+                            new_mode: metadata.mode() & 0o7777,
+                        };
+                    context.journal.push(JournalEntry::new(description, vec![action]));
+                }
+            }
             let curr_tab = &mut context.tabs[context.curr_tab_index];
             curr_tab.refresh_curr(&context.views.mid_win, context.config_t.scroll_offset);
             curr_tab.refresh_file_status(&context.views.bot_win);
         }
+        Ok(())
     }
 }
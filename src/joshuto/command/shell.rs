@@ -0,0 +1,256 @@
+extern crate ncurses;
+
+use std;
+use std::ffi;
+use std::path;
+use std::process;
+use std::thread;
+
+use joshuto::command;
+use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
+use joshuto::command::JoshutoRunnable;
+use joshuto::command::KeymapError;
+use joshuto::command::ReloadDirList;
+use joshuto::context::JoshutoContext;
+use joshuto::pager;
+use joshuto::ui;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShellMode {
+    /* suspend ncurses and wait for the program */
+    Foreground,
+    /* same as Foreground, but wait for Enter before resuming */
+    Wait,
+    /* keep the ui running and don't wait for the program */
+    Background,
+    /* wait for the program without leaving the ui, then show its output in a pager */
+    Pager,
+}
+
+#[derive(Clone, Debug)]
+pub struct ShellCommand {
+    mode: ShellMode,
+    template: Vec<String>,
+}
+
+/* values substituted into the template */
+pub struct ShellPlaceholders {
+    pub selected: Vec<path::PathBuf>,
+    pub curr_file: Option<path::PathBuf>,
+    pub curr_dir: path::PathBuf,
+}
+
+impl ShellCommand {
+    pub fn new(mode: ShellMode, template: Vec<String>) -> Self
+    {
+        ShellCommand {
+            mode,
+            template,
+        }
+    }
+    pub const fn command() -> &'static str { "shell" }
+
+    pub fn from_args(args: &[String]) -> Result<Self, KeymapError>
+    {
+        let mut mode = ShellMode::Foreground;
+        let mut i = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "-w" => mode = ShellMode::Wait,
+                "-b" => mode = ShellMode::Background,
+                "-p" => mode = ShellMode::Pager,
+                _ => break,
+            }
+            i += 1;
+        }
+        if i == args.len() {
+            return Err(KeymapError::new(Some(Self::command()),
+                    String::from("Expected a program to run")));
+        }
+        Ok(ShellCommand::new(mode, args[i..].to_vec()))
+    }
+
+    /* %s selected paths, %f current file, %d current directory,
+     * %n file names of the selected paths, %% a literal %.
+     * Paths are passed on as they are, whether or not they are utf-8 */
+    pub fn expand(template: &[String], placeholders: &ShellPlaceholders) -> Vec<ffi::OsString>
+    {
+        let names: Vec<&ffi::OsStr> = placeholders.selected.iter()
+                .filter_map(|p| p.file_name())
+                .collect();
+        let selected: Vec<&ffi::OsStr> = placeholders.selected.iter()
+                .map(|p| p.as_os_str())
+                .collect();
+        let curr_file: &ffi::OsStr = match placeholders.curr_file.as_ref() {
+                Some(s) => s.as_os_str(),
+                None => ffi::OsStr::new(""),
+            };
+        let curr_dir: &ffi::OsStr = placeholders.curr_dir.as_os_str();
+
+        let mut argv: Vec<ffi::OsString> = Vec::with_capacity(template.len());
+        for arg in template {
+            /* a lone list placeholder becomes one argument per path */
+            match arg.as_str() {
+                "%s" => {
+                    argv.extend(selected.iter().map(|s| s.to_os_string()));
+                    continue;
+                },
+                "%n" => {
+                    argv.extend(names.iter().map(|s| s.to_os_string()));
+                    continue;
+                },
+                _ => {},
+            }
+
+            let mut expanded = ffi::OsString::with_capacity(arg.len());
+            let mut buffer = [0; 4];
+            let mut chars = arg.chars();
+            while let Some(ch) = chars.next() {
+                if ch != '%' {
+                    expanded.push(ch.encode_utf8(&mut buffer));
+                    continue;
+                }
+                match chars.next() {
+                    Some('s') => expanded.push(join_os(&selected)),
+                    Some('n') => expanded.push(join_os(&names)),
+                    Some('f') => expanded.push(curr_file),
+                    Some('d') => expanded.push(curr_dir),
+                    Some('%') => expanded.push("%"),
+                    Some(c) => {
+                        expanded.push("%");
+                        expanded.push(c.encode_utf8(&mut buffer));
+                    },
+                    None => expanded.push("%"),
+                }
+            }
+            argv.push(expanded);
+        }
+        argv
+    }
+
+    fn placeholders(context: &JoshutoContext) -> ShellPlaceholders
+    {
+        let curr_tab = &context.tabs[context.curr_tab_index];
+        let mut selected: Vec<path::PathBuf> = Vec::new();
+        let mut curr_file: Option<path::PathBuf> = None;
+        if let Some(s) = curr_tab.curr_list.as_ref() {
+            if let Some(paths) = command::collect_selected_paths(s) {
+                selected = paths;
+            }
+            if let Some(entry) = s.get_curr_ref() {
+                curr_file = Some(entry.path.clone());
+            }
+        }
+        ShellPlaceholders {
+            selected,
+            curr_file,
+            curr_dir: curr_tab.curr_path.clone(),
+        }
+    }
+
+    fn run_foreground(&self, argv: &[ffi::OsString]) -> Result<(), JoshutoError>
+    {
+        ncurses::savetty();
+        ncurses::endwin();
+        let status = process::Command::new(&argv[0]).args(&argv[1..]).status();
+        if self.mode == ShellMode::Wait {
+            println!("\nPress ENTER to continue");
+            let mut line = String::new();
+            let _ = std::io::stdin().read_line(&mut line);
+        }
+        ncurses::resetty();
+        ncurses::refresh();
+
+        let status = status?;
+        if !status.success() {
+            return Err(JoshutoError::new(format!("{}: {}", argv[0].to_string_lossy(), status)));
+        }
+        Ok(())
+    }
+
+    fn run_background(argv: &[ffi::OsString]) -> Result<(), JoshutoError>
+    {
+        let mut child = process::Command::new(&argv[0]).args(&argv[1..])
+                .stdin(process::Stdio::null())
+                .stdout(process::Stdio::null())
+                .stderr(process::Stdio::null())
+                .spawn()?;
+        /* reap the child once it exits */
+        thread::spawn(move || child.wait());
+        Ok(())
+    }
+
+    fn run_pager(argv: &[ffi::OsString]) -> Result<(), JoshutoError>
+    {
+        let output = process::Command::new(&argv[0]).args(&argv[1..])
+                .stdin(process::Stdio::null())
+                .output()?;
+        let mut lines: Vec<String> = String::from_utf8_lossy(&output.stdout)
+                .lines().map(|s| s.replace('\t', "    ")).collect();
+        lines.extend(String::from_utf8_lossy(&output.stderr)
+                .lines().map(|s| s.replace('\t', "    ")));
+
+        let title = format!("{} ({})", join_os(argv).to_string_lossy(), output.status);
+        pager::show_pager(title.as_str(), &lines);
+        Ok(())
+    }
+}
+
+fn join_os<S: AsRef<ffi::OsStr>>(list: &[S]) -> ffi::OsString
+{
+    let mut joined = ffi::OsString::new();
+    for (i, s) in list.iter().enumerate() {
+        if i > 0 {
+            joined.push(" ");
+        }
+        joined.push(s);
+    }
+    joined
+}
+
+impl JoshutoCommand for ShellCommand {}
+
+impl std::fmt::Display for ShellCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        f.write_str(Self::command())?;
+        match self.mode {
+            ShellMode::Foreground => {},
+            ShellMode::Wait => f.write_str(" -w")?,
+            ShellMode::Background => f.write_str(" -b")?,
+            ShellMode::Pager => f.write_str(" -p")?,
+        }
+        for arg in &self.template {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+impl JoshutoRunnable for ShellCommand {
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        let placeholders = Self::placeholders(context);
+        let argv = Self::expand(&self.template, &placeholders);
+        if argv.is_empty() || argv[0].is_empty() {
+            return Err(JoshutoError::new(format!("{}: nothing to run", Self::command())));
+        }
+
+        let result = match self.mode {
+            ShellMode::Foreground | ShellMode::Wait => self.run_foreground(&argv),
+            ShellMode::Background => Self::run_background(&argv),
+            ShellMode::Pager => Self::run_pager(&argv),
+        };
+
+        ReloadDirList::reload(context);
+        ui::redraw_tab_view(&context.views.tab_win, context);
+        ncurses::doupdate();
+        if self.mode == ShellMode::Background && result.is_ok() {
+            ui::wprint_msg(&context.views.bot_win,
+                    format!("Started in background: {}", argv[0].to_string_lossy()).as_str());
+            ncurses::doupdate();
+        }
+        result
+    }
+}
@@ -3,6 +3,7 @@ extern crate ncurses;
 use std;
 
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
 use joshuto::context::JoshutoContext;
 
@@ -34,7 +35,7 @@ impl std::fmt::Display for ToggleHiddenFiles {
 }
 
 impl JoshutoRunnable for ToggleHiddenFiles {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         Self::toggle_hidden(context);
         let curr_tab = &mut context.tabs[context.curr_tab_index];
@@ -43,5 +44,6 @@ impl JoshutoRunnable for ToggleHiddenFiles {
             &context.username, &context.hostname);
 
         ncurses::doupdate();
+        Ok(())
     }
 }
@@ -5,9 +5,9 @@ use std::env;
 use joshuto::context::JoshutoContext;
 use joshuto::context::JoshutoTab;
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
 use joshuto::command::TabSwitch;
-use joshuto::ui;
 
 #[derive(Clone, Debug)]
 pub struct NewTab;
@@ -16,27 +16,21 @@ impl NewTab {
     pub fn new() -> Self { NewTab }
     pub const fn command() -> &'static str { "new_tab" }
 
-    pub fn new_tab(context: &mut JoshutoContext)
+    pub fn new_tab(context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
-        let curr_path: path::PathBuf = match env::current_dir() {
-            Ok(path) => { path },
-            Err(e) => {
-                ui::wprint_err(&context.views.bot_win, e.to_string().as_str());
-                return;
-            },
-        };
-
-        match JoshutoTab::new(curr_path, &context.config_t.sort_type) {
-            Ok(tab) => {
-                context.tabs.push(tab);
-                context.curr_tab_index = context.tabs.len() - 1;
-
-                TabSwitch::tab_switch(context.tabs.len() as i32 - 1, context);
-            },
-            Err(e) => {
-                ui::wprint_err(&context.views.bot_win, e.to_string().as_str());
-            }
-        };
+        let curr_path: path::PathBuf = env::current_dir()?;
+        Self::new_tab_at(curr_path, context)
+    }
+
+    pub fn new_tab_at(curr_path: path::PathBuf, context: &mut JoshutoContext)
+            -> Result<(), JoshutoError>
+    {
+        let tab = JoshutoTab::new(curr_path, &context.config_t.sort_type)?;
+        context.tabs.push(tab);
+        context.curr_tab_index = context.tabs.len() - 1;
+
+        TabSwitch::tab_switch(context.tabs.len() as i32 - 1, context);
+        Ok(())
     }
 }
 
@@ -50,9 +44,9 @@ impl std::fmt::Display for NewTab {
 }
 
 impl JoshutoRunnable for NewTab {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
-        Self::new_tab(context);
+        Self::new_tab(context)
     }
 }
 
@@ -87,8 +81,9 @@ impl std::fmt::Display for CloseTab {
 }
 
 impl JoshutoRunnable for CloseTab {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         Self::close_tab(context);
+        Ok(())
     }
 }
@@ -2,6 +2,7 @@ use std;
 
 use joshuto::context::JoshutoContext;
 use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
 use joshuto::command::JoshutoRunnable;
 use joshuto::ui;
 
@@ -44,7 +45,7 @@ impl std::fmt::Display for TabSwitch {
 }
 
 impl JoshutoRunnable for TabSwitch {
-    fn execute(&self, context: &mut JoshutoContext)
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
     {
         let mut new_index = context.curr_tab_index as i32 + self.movement;
         let tab_len = context.tabs.len() as i32;
@@ -55,5 +56,17 @@ impl JoshutoRunnable for TabSwitch {
             new_index = new_index - tab_len;
         }
         Self::tab_switch(new_index, context);
+        Ok(())
+    }
+
+    /* with a count, jump to that tab instead */
+    fn execute_count(&self, context: &mut JoshutoContext, count: usize)
+            -> Result<(), JoshutoError>
+    {
+        if count == 0 || count > context.tabs.len() {
+            return Err(JoshutoError::new(format!("No such tab: {}", count)));
+        }
+        Self::tab_switch(count as i32 - 1, context);
+        Ok(())
     }
 }
@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    #[test]
+    fn split_unquoted() {
+        let args = split_shell_style("paste_files overwrite=true");
+        assert_eq!(vec!["paste_files", "overwrite=true"], args);
+        let args = split_shell_style("  tab_switch   2  ");
+        assert_eq!(vec!["tab_switch", "2"], args);
+    }
+    #[test]
+    fn split_quoted() {
+        let args = split_shell_style("cd \"~/my files\" 'a b'");
+        assert_eq!(vec!["cd", "~/my files", "a b"], args);
+        let args = split_shell_style("cd 'unterminated quote");
+        assert_eq!(vec!["cd", "unterminated quote"], args);
+    }
+    #[test]
+    fn cd_expansion() {
+        let expand = |s: &str| -> Vec<String> {
+                wordexp::wordexp(escape_wordexp(s).as_str(), 0).unwrap()
+                    .map(String::from).collect()
+            };
+        assert_eq!(vec!["my dir/it's [1]*"], expand("my dir/it's [1]*"));
+        if let Ok(home) = std::env::var("HOME") {
+            assert_eq!(vec![format!("{}/a b", home)], expand("~/a b"));
+        }
+    }
+    #[test]
+    fn unknown_command() {
+        let aliases = HashMap::new();
+        assert!(from_args("not_a_command", &[], &aliases).is_err());
+        assert!(from_args("tab_switch", &[], &aliases).is_err());
+        assert!(from_args("tab_switch", &[String::from("2")], &aliases).is_ok());
+        assert!(from_args("paste_files", &[String::from("overwrite")], &aliases).is_err());
+        assert!(from_args("select_files", &[String::from("toggle")], &aliases).is_err());
+    }
+    #[test]
+    fn aliases() {
+        let mut aliases = HashMap::new();
+        aliases.insert(String::from("paste_over"), String::from("paste_files overwrite=true"));
+        aliases.insert(String::from("next"), String::from("tab_switch"));
+        aliases.insert(String::from("loop_a"), String::from("loop_b"));
+        aliases.insert(String::from("loop_b"), String::from("chain loop_a"));
+
+        let command = from_args("paste_over", &[], &aliases).unwrap();
+        assert_eq!("paste_files overwrite=true", command.to_string());
+        let command = from_args("next", &[String::from("1")], &aliases).unwrap();
+        assert_eq!("tab_switch 1", command.to_string());
+        assert!(from_args("loop_a", &[], &aliases).is_err());
+    }
+    #[test]
+    fn register_args() {
+        let aliases = HashMap::new();
+        let command = from_args("copy_files", &[String::from("register=a")], &aliases).unwrap();
+        assert_eq!("copy_files register=a", command.to_string());
+        let command = from_args("paste_files", &[String::from("register=3")], &aliases).unwrap();
+        assert_eq!("paste_files register=3", command.to_string());
+        assert!(from_args("cut_files", &[String::from("register=ab")], &aliases).is_err());
+        assert!(from_args("cut_files", &[String::from("register=A")], &aliases).is_err());
+        assert!(from_args("cut_files", &[String::from("all=true")], &aliases).is_err());
+    }
+    #[test]
+    fn paste_link_args() {
+        let aliases = HashMap::new();
+        let args = [String::from("relative=true"), String::from("conflict=rename")];
+        let command = from_args("paste_symlink", &args, &aliases).unwrap();
+        assert_eq!("paste_symlink relative=true conflict=rename", command.to_string());
+        let command = from_args("paste_hardlink", &[String::from("register=b")], &aliases).unwrap();
+        assert_eq!("paste_hardlink register=b", command.to_string());
+        assert!(from_args("paste_hardlink", &[String::from("relative=true")], &aliases).is_err());
+    }
+    #[test]
+    fn shell_placeholders() {
+        use std::path::PathBuf;
+        let placeholders = shell::ShellPlaceholders {
+            selected: vec![PathBuf::from("/tmp/a b.txt"), PathBuf::from("/tmp/c")],
+            curr_file: Some(PathBuf::from("/tmp/c")),
+            curr_dir: PathBuf::from("/tmp"),
+        };
+        let template: Vec<String> = split_shell_style("tar czf %d.tgz %s")
+            .iter().map(|s| s.to_string()).collect();
+        assert_eq!(vec!["tar", "czf", "/tmp.tgz", "/tmp/a b.txt", "/tmp/c"],
+            ShellCommand::expand(&template, &placeholders));
+        let template: Vec<String> = split_shell_style("echo %n '%f' 100%% %x")
+            .iter().map(|s| s.to_string()).collect();
+        assert_eq!(vec!["echo", "a b.txt", "c", "/tmp/c", "100%", "%x"],
+            ShellCommand::expand(&template, &placeholders));
+
+        use std::os::unix::ffi::OsStrExt;
+        let name = std::ffi::OsStr::from_bytes(b"/tmp/caf\xe9");
+        let placeholders = shell::ShellPlaceholders {
+            selected: vec![PathBuf::from(name)],
+            curr_file: None,
+            curr_dir: PathBuf::from("/tmp"),
+        };
+        let template = vec![String::from("%s"), String::from("-%s")];
+        let argv = ShellCommand::expand(&template, &placeholders);
+        assert_eq!(name, argv[0]);
+        assert_eq!(b"-/tmp/caf\xe9", argv[1].as_bytes());
+    }
+}
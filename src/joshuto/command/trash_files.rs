@@ -0,0 +1,256 @@
+extern crate ncurses;
+
+use std;
+use std::fs;
+use std::path;
+
+use joshuto::command;
+use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
+use joshuto::command::JoshutoRunnable;
+use joshuto::command::ReloadDirList;
+use joshuto::config::keymap;
+use joshuto::conflict;
+use joshuto::context::JoshutoContext;
+use joshuto::job::JobHandle;
+use joshuto::job::JobReport;
+use joshuto::job::JobResult;
+use joshuto::job::ProgressInfo;
+use joshuto::job::ProgressUnit;
+use joshuto::journal::FileAction;
+use joshuto::preview;
+use joshuto::trash;
+use joshuto::trash::TrashEntry;
+use joshuto::transfer;
+use joshuto::ui;
+use joshuto::window;
+
+#[derive(Clone, Debug)]
+pub struct TrashFiles;
+
+impl TrashFiles {
+    pub fn new() -> Self { TrashFiles }
+    pub const fn command() -> &'static str { "trash_files" }
+
+    pub fn trash_files(paths: Vec<path::PathBuf>, handle: JobHandle) -> JobResult
+    {
+        let mut report = JobReport::new();
+        let mut progress_info = ProgressInfo {
+                bytes_finished: 0,
+                total_bytes: paths.len() as u64,
+            };
+        for path in &paths {
+            if !handle.check() {
+                break;
+            }
+            let result = trash::trash_dir_for(path).and_then(|s| s.trash(path))
+                    .map(|s| handle.record(FileAction::Trash(s, None)));
+            report.add(path, result);
+            progress_info.bytes_finished += 1;
+            handle.progress(progress_info.clone());
+        }
+        Ok(report)
+    }
+}
+
+impl JoshutoCommand for TrashFiles {}
+
+impl std::fmt::Display for TrashFiles {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        f.write_str(Self::command())
+    }
+}
+
+impl JoshutoRunnable for TrashFiles {
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        let paths = match context.tabs[context.curr_tab_index].curr_list.as_ref() {
+                Some(s) => command::collect_selected_paths(s),
+                None => None,
+            };
+        if let Some(paths) = paths {
+            let description = format!("trash {} file(s)", paths.len());
+            let total = paths.len() as u64;
+            let id = context.jobs.spawn(description.clone(), ProgressUnit::Files, total,
+                    move |handle| Self::trash_files(paths, handle));
+            ui::wprint_msg(&context.views.bot_win,
+                    format!("Job {} started: {}", id, description).as_str());
+        }
+        ncurses::doupdate();
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ShowTrash;
+
+impl ShowTrash {
+    pub fn new() -> Self { ShowTrash }
+    pub const fn command() -> &'static str { "trash" }
+
+    fn entries() -> Vec<TrashEntry>
+    {
+        let mut entries: Vec<TrashEntry> = trash::trash_dirs().iter()
+                .flat_map(|s| s.entries())
+                .collect();
+        entries.sort_by(|a, b| b.deletion_date.cmp(&a.deletion_date));
+        entries
+    }
+
+    /* an info file is only removed once its file is gone, so nothing
+     * is left in the trash without its info */
+    pub fn empty_trash(entries: Vec<TrashEntry>, handle: JobHandle) -> JobResult
+    {
+        let mut report = JobReport::new();
+        let mut progress_info = ProgressInfo {
+                bytes_finished: 0,
+                total_bytes: entries.len() as u64,
+            };
+        for entry in &entries {
+            if !handle.check() {
+                break;
+            }
+            let failed = report.errors.len();
+            if conflict::path_exists(&entry.trash_path) {
+                transfer::remove_path(&entry.trash_path, &mut report);
+            }
+            if report.errors.len() == failed {
+                if let Err(e) = fs::remove_file(&entry.info_path) {
+                    report.add(&entry.info_path, Err(e));
+                }
+            }
+            progress_info.bytes_finished += 1;
+            handle.progress(progress_info.clone());
+        }
+        Ok(report)
+    }
+
+    fn confirm(win: &window::JoshutoPanel, row: i32, cols: i32, prompt: &str) -> bool
+    {
+        ncurses::wmove(win.win, row, 0);
+        ncurses::wclrtoeol(win.win);
+        ncurses::mvwaddnstr(win.win, row, 0, prompt, cols);
+        win.queue_for_refresh();
+        ncurses::doupdate();
+        match ncurses::wget_wch(win.win) {
+            Some(ncurses::WchResult::Char(s)) => s == 'y' as u32,
+            _ => false,
+        }
+    }
+
+    /* full screen list of trashed files, most recently deleted first.
+     * Restoring and deleting here aren't journaled: a deleted file is gone
+     * for good, and a restored one can be trashed again with trash_files */
+    fn show_trash(context: &mut JoshutoContext) -> Option<String>
+    {
+        const FOOTER: &str = "r restore, d delete, E empty trash, q close";
+
+        let (term_rows, term_cols) = ui::getmaxyx();
+        let win = window::JoshutoPanel::new(term_rows, term_cols, (0, 0));
+        ncurses::keypad(win.win, true);
+        win.move_to_top();
+
+        let mut entries = Self::entries();
+        let mut index: usize = 0;
+        let mut status_msg: Option<String> = None;
+        let mut started: Option<String> = None;
+        loop {
+            index = index.min(entries.len().saturating_sub(1));
+            let list_rows = (term_rows - 2).max(1) as usize;
+            let offset = (index + 1).saturating_sub(list_rows);
+
+            ncurses::werase(win.win);
+            ncurses::wattron(win.win, ncurses::A_BOLD());
+            ncurses::mvwaddnstr(win.win, 0, 0, "Trash", term_cols);
+            ncurses::wattroff(win.win, ncurses::A_BOLD());
+            for (i, entry) in entries.iter().enumerate().skip(offset).take(list_rows) {
+                if i == index {
+                    ncurses::wattron(win.win, ncurses::A_STANDOUT());
+                }
+                let line = format!("{:<19}  {}", entry.deletion_date,
+                        entry.original_path.to_string_lossy());
+                ncurses::mvwaddnstr(win.win, (i - offset) as i32 + 1, 0,
+                        line.as_str(), term_cols);
+                if i == index {
+                    ncurses::wattroff(win.win, ncurses::A_STANDOUT());
+                }
+            }
+            if entries.is_empty() {
+                ncurses::mvwaddnstr(win.win, 1, 0, "Trash is empty", term_cols);
+            }
+            let footer = match status_msg.as_ref() {
+                    Some(s) => s.as_str(),
+                    None => FOOTER,
+                };
+            ncurses::mvwaddnstr(win.win, term_rows - 1, 0, footer, term_cols);
+            win.queue_for_refresh();
+            ncurses::doupdate();
+
+            let ch = match ncurses::wget_wch(win.win) {
+                    Some(s) => keymap::wch_to_key(s),
+                    None => continue,
+                };
+            status_msg = None;
+            if ch == keymap::ESCAPE || ch == 'q' as i32 {
+                break;
+            } else if ch == keymap::keycode(ncurses::KEY_UP) || ch == 'k' as i32 {
+                index = index.saturating_sub(1);
+            } else if ch == keymap::keycode(ncurses::KEY_DOWN) || ch == 'j' as i32 {
+                index += 1;
+            } else if ch == 'E' as i32 {
+                if entries.is_empty()
+                        || !Self::confirm(&win, term_rows - 1, term_cols, "Empty trash? (y/N)") {
+                    continue;
+                }
+                let description = format!("empty trash of {} file(s)", entries.len());
+                let total = entries.len() as u64;
+                let id = context.jobs.spawn(description.clone(), ProgressUnit::Files, total,
+                        move |handle| Self::empty_trash(entries, handle));
+                started = Some(format!("Job {} started: {}", id, description));
+                break;
+            } else if let Some(entry) = entries.get(index) {
+                let result = if ch == 'r' as i32 {
+                        entry.restore()
+                    } else if ch == 'd' as i32 {
+                        let prompt = format!("Delete {} permanently? (y/N)",
+                                entry.original_path.to_string_lossy());
+                        if !Self::confirm(&win, term_rows - 1, term_cols, prompt.as_str()) {
+                            continue;
+                        }
+                        entry.delete()
+                    } else {
+                        continue;
+                    };
+                match result {
+                    Ok(_) => { entries.remove(index); },
+                    Err(e) => status_msg = Some(e.to_string()),
+                }
+            }
+        }
+        started
+    }
+}
+
+impl JoshutoCommand for ShowTrash {}
+
+impl std::fmt::Display for ShowTrash {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        f.write_str(Self::command())
+    }
+}
+
+impl JoshutoRunnable for ShowTrash {
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        let started = Self::show_trash(context);
+        ReloadDirList::reload(context);
+        preview::preview_file(context);
+        if let Some(s) = started {
+            ui::wprint_msg(&context.views.bot_win, s.as_str());
+        }
+        ncurses::doupdate();
+        Ok(())
+    }
+}
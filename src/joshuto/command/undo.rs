@@ -0,0 +1,104 @@
+extern crate ncurses;
+
+use std;
+
+use joshuto::command::JoshutoCommand;
+use joshuto::command::JoshutoError;
+use joshuto::command::JoshutoRunnable;
+use joshuto::command::ReloadDirList;
+use joshuto::context::JoshutoContext;
+use joshuto::preview;
+use joshuto::ui;
+
+/* steps back through the journal count times, then shows what happened */
+fn step(context: &mut JoshutoContext, count: usize, redo: bool) -> Result<(), JoshutoError>
+{
+    let mut done: Vec<String> = Vec::new();
+    let mut result: Result<(), JoshutoError> = Ok(());
+    for _ in 0..count {
+        let step_result = if redo {
+                context.journal.redo()
+            } else {
+                context.journal.undo()
+            };
+        match step_result {
+            Ok(s) => done.push(s),
+            Err(e) => {
+                result = Err(JoshutoError::new(e));
+                break;
+            },
+        }
+    }
+
+    if !done.is_empty() {
+        ReloadDirList::reload(context);
+        preview::preview_file(context);
+        if result.is_ok() {
+            let msg = format!("{}: {}", if redo { "Redone" } else { "Undone" },
+                    done.join(", "));
+            ui::wprint_msg(&context.views.bot_win, msg.as_str());
+        }
+    }
+    ncurses::doupdate();
+    result
+}
+
+#[derive(Clone, Debug)]
+pub struct Undo;
+
+impl Undo {
+    pub fn new() -> Self { Undo }
+    pub const fn command() -> &'static str { "undo" }
+}
+
+impl JoshutoCommand for Undo {}
+
+impl std::fmt::Display for Undo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        f.write_str(Self::command())
+    }
+}
+
+impl JoshutoRunnable for Undo {
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        step(context, 1, false)
+    }
+
+    fn execute_count(&self, context: &mut JoshutoContext, count: usize)
+            -> Result<(), JoshutoError>
+    {
+        step(context, count, false)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Redo;
+
+impl Redo {
+    pub fn new() -> Self { Redo }
+    pub const fn command() -> &'static str { "redo" }
+}
+
+impl JoshutoCommand for Redo {}
+
+impl std::fmt::Display for Redo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        f.write_str(Self::command())
+    }
+}
+
+impl JoshutoRunnable for Redo {
+    fn execute(&self, context: &mut JoshutoContext) -> Result<(), JoshutoError>
+    {
+        step(context, 1, true)
+    }
+
+    fn execute_count(&self, context: &mut JoshutoContext, count: usize)
+            -> Result<(), JoshutoError>
+    {
+        step(context, count, true)
+    }
+}
@@ -0,0 +1,193 @@
+use std::env;
+use std::fs;
+use std::path;
+
+use joshuto::command;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Clone, Debug)]
+pub enum CompletionKind {
+    /* the whole line is a single path */
+    FileName,
+    /* paths split the way command::split_shell_style does */
+    Arguments,
+    /* a command name followed by its arguments */
+    Command,
+}
+
+#[derive(Clone, Debug)]
+pub struct JoshutoCompleter {
+    pub kind: CompletionKind,
+    pub base_path: path::PathBuf,
+}
+
+impl JoshutoCompleter {
+    pub fn new(kind: CompletionKind, base_path: path::PathBuf) -> Self
+    {
+        JoshutoCompleter {
+            kind,
+            base_path,
+        }
+    }
+
+    /* returns the char index where the word being completed starts,
+     * along with every candidate that can replace it */
+    pub fn complete(&self, buffer: &[char], curr_index: usize) -> (usize, Vec<String>)
+    {
+        let (word_start, quote) = match self.kind {
+            CompletionKind::FileName => (0, None),
+            _ => last_word(&buffer[..curr_index]),
+        };
+        let word: String = match quote {
+            Some(q) => {
+                let word = &buffer[word_start + 1..curr_index];
+                match word.split_last() {
+                    Some((ch, rest)) if *ch == q => rest.iter().collect(),
+                    _ => word.iter().collect(),
+                }
+            },
+            None => buffer[word_start..curr_index].iter().collect(),
+        };
+
+        let candidates = match self.kind {
+            CompletionKind::Command => {
+                let preceding: String = buffer[..word_start].iter().collect();
+                match command::split_shell_style(preceding.as_str()).first() {
+                    None => complete_from_list(command::COMMANDS, &word),
+                    Some(command_name) => {
+                        let mut candidates = complete_from_list(
+                                command::command_args(command_name), &word);
+                        candidates.extend(complete_path(&self.base_path, &word));
+                        candidates
+                    },
+                }
+            },
+            _ => complete_path(&self.base_path, &word),
+        };
+        let candidates = match self.kind {
+            CompletionKind::FileName => candidates,
+            _ => candidates.into_iter().map(|s| quote_word(s, quote)).collect(),
+        };
+        (word_start, candidates)
+    }
+}
+
+/* where the last word of line starts and the quote it starts with, if any.
+ * Split the way command::split_shell_style does: a word starting with a
+ * quote runs to the next one, anything else to the next whitespace */
+fn last_word(line: &[char]) -> (usize, Option<char>)
+{
+    let mut word_start = 0;
+    let mut quote = None;
+    let mut i = 0;
+    while i < line.len() {
+        let ch = line[i];
+        if ch.is_whitespace() {
+            i += 1;
+            word_start = i;
+            quote = None;
+        } else if ch == '\'' || ch == '"' {
+            word_start = i;
+            quote = Some(ch);
+            i += 1;
+            while i < line.len() && line[i] != ch {
+                i += 1;
+            }
+            i += 1;
+        } else {
+            word_start = i;
+            quote = None;
+            while i < line.len() && !line[i].is_whitespace() {
+                i += 1;
+            }
+        }
+    }
+    (word_start, quote)
+}
+
+/* quote candidate if it has to be, or if the word it completes was.
+ * A directory's quote is left open so the path can be carried on */
+fn quote_word(candidate: String, quote: Option<char>) -> String
+{
+    if quote.is_none() && !candidate.starts_with(['\'', '"'])
+            && !candidate.contains(char::is_whitespace) {
+        return candidate;
+    }
+    let quote = match quote {
+        Some(q) if !candidate.contains(q) => q,
+        _ if !candidate.contains('\'') => '\'',
+        _ if !candidate.contains('"') => '"',
+        /* no quote can hold it */
+        _ => return candidate,
+    };
+    if candidate.ends_with('/') {
+        format!("{}{}", quote, candidate)
+    } else {
+        format!("{}{}{}", quote, candidate, quote)
+    }
+}
+
+fn complete_from_list(list: &[&str], word: &str) -> Vec<String>
+{
+    list.iter().filter(|s| s.starts_with(word))
+        .map(|s| s.to_string()).collect()
+}
+
+fn complete_path(base_path: &path::Path, word: &str) -> Vec<String>
+{
+    let (dir_part, file_prefix) = match word.rfind('/') {
+        Some(i) => (&word[..=i], &word[i+1..]),
+        None => ("", word),
+    };
+
+    let search_dir: path::PathBuf = if dir_part.is_empty() {
+        base_path.to_path_buf()
+    } else if let Some(rest) = dir_part.strip_prefix("~/") {
+        match env::var_os("HOME") {
+            Some(home) => path::PathBuf::from(home).join(rest),
+            None => return Vec::new(),
+        }
+    } else {
+        base_path.join(dir_part)
+    };
+
+    let read_dir = match fs::read_dir(&search_dir) {
+        Ok(s) => s,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut candidates: Vec<String> = read_dir
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            if !file_name.starts_with(file_prefix) {
+                return None;
+            }
+            if file_name.starts_with('.') && !file_prefix.starts_with('.') {
+                return None;
+            }
+            let mut candidate = format!("{}{}", dir_part, file_name);
+            if entry.path().is_dir() {
+                candidate.push('/');
+            }
+            Some(candidate)
+        }).collect();
+    candidates.sort();
+    candidates
+}
+
+pub fn common_prefix(candidates: &[String]) -> String
+{
+    let mut prefix: Vec<char> = match candidates.first() {
+        Some(s) => s.chars().collect(),
+        None => return String::new(),
+    };
+    for candidate in &candidates[1..] {
+        let common_len = prefix.iter().zip(candidate.chars())
+                .take_while(|(a, b)| *a == b).count();
+        prefix.truncate(common_len);
+    }
+    prefix.iter().collect()
+}
@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use joshuto::test_helpers::temp_dir;
+
+    fn complete_line(completer: &JoshutoCompleter, line: &str) -> (usize, Vec<String>)
+    {
+        let buffer: Vec<char> = line.chars().collect();
+        completer.complete(&buffer, buffer.len())
+    }
+
+    #[test]
+    fn split_last_word() {
+        let line: Vec<char> = "cd 'my dir/a b".chars().collect();
+        assert_eq!((3, Some('\'')), last_word(&line));
+        let line: Vec<char> = "cd \"a b\" c".chars().collect();
+        assert_eq!((9, None), last_word(&line));
+        let line: Vec<char> = "cd \"a b\" ".chars().collect();
+        assert_eq!((9, None), last_word(&line));
+    }
+
+    #[test]
+    fn quoted_candidates() {
+        let dir = temp_dir("completion");
+        fs::create_dir_all(dir.join("my dir")).unwrap();
+        fs::write(dir.join("my dir/a file"), "").unwrap();
+        fs::write(dir.join("plain"), "").unwrap();
+        let completer = JoshutoCompleter::new(CompletionKind::Command, dir.clone());
+
+        assert_eq!((3, vec![String::from("'my dir/")]), complete_line(&completer, "cd my"));
+        assert_eq!((3, vec![String::from("'my dir/a file'")]),
+                complete_line(&completer, "cd 'my dir/"));
+        assert_eq!((3, vec![String::from("\"my dir/a file\"")]),
+                complete_line(&completer, "cd \"my dir/a"));
+        assert_eq!((3, vec![String::from("plain")]), complete_line(&completer, "cd pl"));
+
+        let (start, candidates) = complete_line(&completer, "cd 'my dir/a");
+        let line = format!("cd {}", candidates[0]);
+        assert_eq!(3, start);
+        assert_eq!(vec!["cd", "my dir/a file"], command::split_shell_style(line.as_str()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,10 +1,12 @@
 pub mod config;
 pub mod keymap;
 pub mod mimetype;
+pub mod parse;
 pub mod theme;
 
 pub use self::config::JoshutoConfig;
 pub use self::keymap::JoshutoKeymap;
 pub use self::mimetype::JoshutoMimetype;
+pub use self::parse::ConfigError;
 pub use self::theme::JoshutoTheme;
 pub use self::theme::JoshutoColorTheme;
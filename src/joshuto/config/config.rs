@@ -2,11 +2,17 @@ extern crate whoami;
 extern crate toml;
 extern crate xdg;
 
-use std::fs;
-use std::process;
+use std::collections::HashMap;
 
 use joshuto;
+use joshuto::config::parse;
+use joshuto::conflict;
+use joshuto::conflict::ConflictPolicy;
+use joshuto::config::ConfigError;
 use joshuto::sort;
+use joshuto::transfer::MoveVerify;
+
+pub const DEFAULT_CONFIG: &str = include_str!("../../../config/joshuto.toml");
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct SortRawOption {
@@ -16,23 +22,93 @@ pub struct SortRawOption {
     pub reverse: Option<bool>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+impl SortRawOption {
+    pub fn merge(&mut self, layer: SortRawOption)
+    {
+        self.show_hidden = layer.show_hidden.or(self.show_hidden);
+        self.directories_first = layer.directories_first.or(self.directories_first);
+        self.case_sensitive = layer.case_sensitive.or(self.case_sensitive);
+        self.reverse = layer.reverse.or(self.reverse);
+    }
+}
+
+#[derive(Debug, Deserialize)]
 pub struct JoshutoRawConfig {
     scroll_offset: Option<usize>,
-    sort_type: Option<String>,
+    sort_type: Option<toml::Spanned<String>>,
     sort_option: Option<SortRawOption>,
     column_ratio: Option<[usize; 3]>,
+    aliases: Option<HashMap<String, String>>,
+    startup: Option<Vec<String>>,
+    paste_conflict: Option<toml::Spanned<String>>,
+    rename_pattern: Option<toml::Spanned<String>>,
+    verify_move: Option<toml::Spanned<String>>,
 }
 
 impl JoshutoRawConfig {
-    #[allow(dead_code)]
     pub fn new() -> Self
     {
         JoshutoRawConfig {
-            scroll_offset: Some(8),
-            sort_type: Some(String::from("natural")),
+            scroll_offset: None,
+            sort_type: None,
             sort_option: None,
-            column_ratio: Some([1, 3, 4]),
+            column_ratio: None,
+            aliases: None,
+            startup: None,
+            paste_conflict: None,
+            rename_pattern: None,
+            verify_move: None,
+        }
+    }
+
+    /* values set in layer replace the ones in self */
+    pub fn merge(&mut self, layer: JoshutoRawConfig, errors: &mut Vec<ConfigError>)
+    {
+        if let Some(s) = layer.sort_type {
+            match s.get_ref().as_str() {
+                "natural" | "mtime" => self.sort_type = Some(s),
+                other => {
+                    errors.push(ConfigError::at(s.start(), Some(String::from("sort_type")),
+                            format!("Unknown sort type: {}", other)));
+                },
+            }
+        }
+        if let Some(s) = layer.paste_conflict {
+            match ConflictPolicy::parse(s.get_ref()) {
+                Some(_) => self.paste_conflict = Some(s),
+                None => {
+                    errors.push(ConfigError::at(s.start(), Some(String::from("paste_conflict")),
+                            format!("Unknown conflict policy: {}", s.get_ref())));
+                },
+            }
+        }
+        if let Some(s) = layer.rename_pattern {
+            if s.get_ref().contains("{n}") {
+                self.rename_pattern = Some(s);
+            } else {
+                errors.push(ConfigError::at(s.start(), Some(String::from("rename_pattern")),
+                        String::from("Pattern needs {n} to number renamed files")));
+            }
+        }
+        if let Some(s) = layer.verify_move {
+            match MoveVerify::parse(s.get_ref()) {
+                Some(_) => self.verify_move = Some(s),
+                None => {
+                    errors.push(ConfigError::at(s.start(), Some(String::from("verify_move")),
+                            format!("Unknown verification: {}", s.get_ref())));
+                },
+            }
+        }
+        self.scroll_offset = layer.scroll_offset.or(self.scroll_offset);
+        self.column_ratio = layer.column_ratio.or(self.column_ratio);
+        self.startup = layer.startup.or(self.startup.take());
+        match (self.sort_option.as_mut(), layer.sort_option) {
+            (Some(s), Some(layer)) => s.merge(layer),
+            (None, Some(layer)) => self.sort_option = Some(layer),
+            _ => {},
+        }
+        if let Some(aliases) = layer.aliases {
+            self.aliases.get_or_insert_with(HashMap::new).extend(aliases);
         }
     }
 
@@ -74,7 +150,7 @@ impl JoshutoRawConfig {
 
         let sort_type: sort::SortType = match self.sort_type {
             Some(s) => {
-                match s.as_str() {
+                match s.get_ref().as_str() {
                     "natural" => sort::SortType::SortNatural(sort_option),
                     "mtime" => sort::SortType::SortMtime(sort_option),
                     _ => sort::SortType::SortNatural(sort_option),
@@ -83,10 +159,28 @@ impl JoshutoRawConfig {
             _ => sort::SortType::SortNatural(sort_option),
             };
 
+        let aliases = self.aliases.unwrap_or_default();
+        let startup = self.startup.unwrap_or_default();
+        let paste_conflict = self.paste_conflict
+                .and_then(|s| ConflictPolicy::parse(s.get_ref()))
+                .unwrap_or(ConflictPolicy::Ask);
+        let rename_pattern = match self.rename_pattern {
+                Some(s) => s.into_inner(),
+                None => String::from(conflict::DEFAULT_RENAME_PATTERN),
+            };
+        let verify_move = self.verify_move
+                .and_then(|s| MoveVerify::parse(s.get_ref()))
+                .unwrap_or(MoveVerify::Size);
+
         JoshutoConfig {
             scroll_offset,
             sort_type,
             column_ratio,
+            aliases,
+            startup,
+            paste_conflict,
+            rename_pattern,
+            verify_move,
         }
     }
 }
@@ -96,10 +190,19 @@ pub struct JoshutoConfig {
     pub scroll_offset: usize,
     pub sort_type: joshuto::sort::SortType,
     pub column_ratio: (usize, usize, usize),
+    pub aliases: HashMap<String, String>,
+    /* command lines run after the first tab is opened */
+    pub startup: Vec<String>,
+    /* what paste_files does when a name is taken, unless given */
+    pub paste_conflict: ConflictPolicy,
+    pub rename_pattern: String,
+    /* how a copy is checked when a move crosses filesystems */
+    pub verify_move: MoveVerify,
 }
 
 impl JoshutoConfig {
 
+    #[allow(dead_code)]
     pub fn new() -> Self
     {
         let sort_option = sort::SortOption {
@@ -114,48 +217,17 @@ impl JoshutoConfig {
             scroll_offset: 6,
             sort_type,
             column_ratio: (1, 3, 4),
+            aliases: HashMap::new(),
+            startup: Vec::new(),
+            paste_conflict: ConflictPolicy::Ask,
+            rename_pattern: String::from(conflict::DEFAULT_RENAME_PATTERN),
+            verify_move: MoveVerify::Size,
         }
     }
 
-    fn read_config() -> Option<JoshutoRawConfig>
-    {
-        match xdg::BaseDirectories::with_profile(::PROGRAM_NAME, "") {
-            Ok(dirs) => {
-                let config_path = dirs.find_config_file(::CONFIG_FILE)?;
-                match fs::read_to_string(&config_path) {
-                    Ok(config_contents) => {
-                        match toml::from_str(&config_contents) {
-                            Ok(config) => {
-                                Some(config)
-                            },
-                            Err(e) => {
-                                eprintln!("Error parsing keymap file: {}", e);
-                                process::exit(1);
-                            },
-                        }
-                    },
-                    Err(e) => {
-                        eprintln!("{}", e);
-                        None
-                    },
-                }
-            },
-            Err(e) => {
-                eprintln!("{}", e);
-                None
-            },
-        }
-    }
-
-    pub fn get_config() -> Self
+    pub fn load(errors: &mut Vec<ConfigError>) -> Self
     {
-        match Self::read_config() {
-            Some(config) => {
-                config.flatten()
-            }
-            None => {
-                JoshutoConfig::new()
-            }
-        }
+        parse::parse_config_layers(::CONFIG_FILE, DEFAULT_CONFIG, errors,
+                JoshutoRawConfig::new(), JoshutoRawConfig::merge).flatten()
     }
 }
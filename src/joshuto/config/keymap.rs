@@ -2,45 +2,117 @@ extern crate fs_extra;
 extern crate toml;
 extern crate xdg;
 
+use std;
 use std::collections::HashMap;
-use std::fs;
-use std::process;
 
 use joshuto::command;
+use joshuto::config::parse;
+use joshuto::config::ConfigError;
+
+#[cfg(test)]
+mod test;
+
+pub const DEFAULT_KEYMAP: &str = include_str!("../../../config/keymap.toml");
 
 pub const BACKSPACE: i32 = 0x7F;
 pub const TAB: i32 = 0x9;
 pub const ENTER: i32 = 0xA;
 pub const ESCAPE: i32 = 0x1B;
 
+/* ncurses key codes overlap characters from U+0101 on,
+ * so they are moved past the last unicode character */
+const KEYCODE_OFFSET: i32 = 0x110000;
+
+pub const fn keycode(key: i32) -> i32
+{
+    key + KEYCODE_OFFSET
+}
+
+/* a key read with get_wch, as keymaps store it */
+pub fn wch_to_key(wch: ncurses::WchResult) -> i32
+{
+    match wch {
+        ncurses::WchResult::Char(s) => s as i32,
+        ncurses::WchResult::KeyCode(s) => keycode(s),
+    }
+}
+
 /* #define KEY_ALT(x) KEY_F(60) + (x - 'A') */
 
 #[derive(Debug, Deserialize)]
 struct JoshutoMapCommand {
-    pub keys: Vec<String>,
-    pub command: String,
+    pub keys: toml::Spanned<Vec<String>>,
+    pub command: Option<String>,
     pub args: Option<Vec<String>>,
+    /* command lines run one after another */
+    pub commands: Option<Vec<String>>,
+}
+
+impl JoshutoMapCommand {
+    fn to_command(&self, aliases: &HashMap<String, String>)
+            -> Result<Box<dyn command::JoshutoCommand>, command::KeymapError>
+    {
+        match (self.command.as_ref(), self.commands.as_ref()) {
+            (Some(s), None) => {
+                let args: &[String] = match self.args.as_ref() {
+                        Some(s) => s,
+                        None => &[],
+                    };
+                command::from_args(s.as_str(), args, aliases)
+            },
+            (None, Some(s)) => {
+                let chain = command::CommandChain::from_lines(s, aliases)?;
+                Ok(Box::new(chain))
+            },
+            _ => Err(command::KeymapError::new(None,
+                    String::from("Expected either command or commands"))),
+        }
+    }
 }
 
 
 #[derive(Debug, Deserialize)]
-struct JoshutoRawKeymap {
+pub struct JoshutoRawKeymap {
     mapcommand: Option<Vec<JoshutoMapCommand>>,
 }
 
 impl JoshutoRawKeymap {
 
-    pub fn flatten(self) -> JoshutoKeymap
+    pub fn flatten(self, aliases: &HashMap<String, String>, errors: &mut Vec<ConfigError>)
+            -> JoshutoKeymap
     {
         let mut keymaps: HashMap<i32, command::CommandKeybind> = HashMap::new();
         if let Some(maps) = self.mapcommand {
             for mapcommand in maps {
-                match command::from_args(mapcommand.command.as_str(), mapcommand.args.as_ref()) {
-                    Some(command) => {
-                        insert_keycommand(&mut keymaps, command, &mapcommand.keys[..]);
+                let offset = mapcommand.keys.start();
+                let key = Some(mapcommand.keys.get_ref().join(" "));
+
+                let mut keys: Vec<i32> = Vec::with_capacity(mapcommand.keys.get_ref().len());
+                for keycode in mapcommand.keys.get_ref() {
+                    match str_to_keys(keycode) {
+                        Ok(s) => keys.extend(s),
+                        Err(e) => {
+                            errors.push(ConfigError::at(offset, key.clone(), e));
+                            keys.clear();
+                            break;
+                        },
+                    }
+                }
+                if keys.is_empty() {
+                    if mapcommand.keys.get_ref().is_empty() {
+                        errors.push(ConfigError::at(offset, key,
+                                String::from("Keybinding without keys")));
+                    }
+                    continue;
+                }
+                match mapcommand.to_command(aliases) {
+                    Ok(command) => {
+                        if let Err(e) = insert_keycommand(&mut keymaps, command, &keys) {
+                            errors.push(ConfigError::at(offset, key, e));
+                        }
                     },
-                    None => {
-                        println!("Unknown command: {}", mapcommand.command);
+                    Err(e) => {
+                        errors.push(ConfigError::at(offset, key, e.to_string()));
                     }
                 }
             }
@@ -65,134 +137,223 @@ impl JoshutoKeymap {
         }
     }
 
-    fn read_config() -> Option<JoshutoRawKeymap>
+    /* every keybinding as its key sequence and command, sorted by keys */
+    pub fn bindings(&self) -> Vec<(String, String)>
     {
-        match xdg::BaseDirectories::with_profile(::PROGRAM_NAME, "") {
-            Ok(dirs) => {
-                let config_path = dirs.find_config_file(::KEYMAP_FILE)?;
-                match fs::read_to_string(&config_path) {
-                    Ok(config_contents) => {
-                        match toml::from_str(&config_contents) {
-                            Ok(config) => {
-                                Some(config)
-                            },
-                            Err(e) => {
-                                eprintln!("Error parsing keymap file: {}", e);
-                                process::exit(1);
-                            },
-                        }
+        fn flatten_(keymaps: &HashMap<i32, command::CommandKeybind>, prefix: &str,
+                bindings: &mut Vec<(String, String)>)
+        {
+            for (key, val) in keymaps {
+                let keys = if prefix.is_empty() {
+                        key_to_string(*key)
+                    } else {
+                        format!("{} {}", prefix, key_to_string(*key))
+                    };
+                match val {
+                    command::CommandKeybind::SimpleKeybind(s) => {
+                        bindings.push((keys, s.to_string()));
                     },
-                    Err(e) => {
-                        eprintln!("{}", e);
-                        process::exit(1);
+                    command::CommandKeybind::CompositeKeybind(m) => {
+                        flatten_(m, keys.as_str(), bindings);
                     },
                 }
-            },
-            Err(e) => {
-                eprintln!("{}", e);
-                None
-            },
+            }
         }
+
+        let mut bindings: Vec<(String, String)> = Vec::new();
+        flatten_(&self.keymaps, "", &mut bindings);
+        bindings.sort();
+        bindings
     }
 
-    pub fn get_config() -> JoshutoKeymap
+    pub fn load(aliases: &HashMap<String, String>, errors: &mut Vec<ConfigError>) -> Self
     {
-        match JoshutoKeymap::read_config() {
-            Some(config) => {
-                config.flatten()
-            }
-            None => {
-                JoshutoKeymap::new()
-            }
-        }
+        parse::parse_config_layers(::KEYMAP_FILE, DEFAULT_KEYMAP, errors, JoshutoKeymap::new(),
+                |keymap: &mut JoshutoKeymap, raw: JoshutoRawKeymap, errors| {
+                    let layer = raw.flatten(aliases, errors);
+                    merge_keymaps(&mut keymap.keymaps, layer.keymaps);
+                })
     }
 }
 
+/* keybindings in layer replace the ones in map with the same keys,
+ * prefixes shared by both are merged */
+fn merge_keymaps(map: &mut HashMap<i32, command::CommandKeybind>,
+        layer: HashMap<i32, command::CommandKeybind>)
+{
+    for (key, val) in layer {
+        match (map.remove(&key), val) {
+            (Some(command::CommandKeybind::CompositeKeybind(mut m)),
+                    command::CommandKeybind::CompositeKeybind(l)) => {
+                merge_keymaps(&mut m, l);
+                map.insert(key, command::CommandKeybind::CompositeKeybind(m));
+            },
+            (_, val) => {
+                map.insert(key, val);
+            },
+        }
+    }
+}
 
 fn insert_keycommand(map: &mut HashMap<i32, command::CommandKeybind>,
-        keycommand: Box<dyn command::JoshutoCommand>, keys: &[String])
+        keycommand: Box<dyn command::JoshutoCommand>, keys: &[i32]) -> Result<(), String>
 {
     if keys.len() == 1 {
-        match key_to_i32(&keys[0]) {
-            Some(s) => {
-                map.insert(s, command::CommandKeybind::SimpleKeybind(keycommand));
-            },
-            None => {}
+        if let Some(command::CommandKeybind::CompositeKeybind(_)) = map.get(&keys[0]) {
+            return Err(format!("Keybindings ambiguous: {} is a prefix of other keybindings",
+                    key_to_string(keys[0])));
         }
+        map.insert(keys[0], command::CommandKeybind::SimpleKeybind(keycommand));
+        Ok(())
     } else {
-        match key_to_i32(&keys[0]) {
-            Some(s) => {
-                let mut new_map: HashMap<i32, command::CommandKeybind>;
-                match map.remove(&s) {
-                    Some(command::CommandKeybind::CompositeKeybind(mut m)) => {
-                        new_map = m;
-                    },
-                    Some(_) => {
-                        eprintln!("Error: Keybindings ambiguous");
-                        process::exit(1);
-                    },
-                    None => {
-                        new_map = HashMap::new();
-                    }
-                }
-                insert_keycommand(&mut new_map, keycommand, &keys[1..]);
-                let composite_command = command::CommandKeybind::CompositeKeybind(new_map);
-                map.insert(s as i32, composite_command);
-            },
-            None => {}
-        }
+        let mut new_map: HashMap<i32, command::CommandKeybind> = match map.remove(&keys[0]) {
+                Some(command::CommandKeybind::CompositeKeybind(m)) => m,
+                Some(s) => {
+                    map.insert(keys[0], s);
+                    return Err(format!("Keybindings ambiguous: {} is already bound",
+                            key_to_string(keys[0])));
+                },
+                None => HashMap::new(),
+            };
+        let result = insert_keycommand(&mut new_map, keycommand, &keys[1..]);
+        map.insert(keys[0], command::CommandKeybind::CompositeKeybind(new_map));
+        result
     }
 }
 
+/* names of keys that aren't a single character */
+const KEY_NAMES: &[(&str, i32)] = &[
+    ("Tab", TAB),
+    ("Space", ' ' as i32),
+    ("Backspace", BACKSPACE),
+    ("Delete", keycode(ncurses::KEY_DC)),
+    ("Enter", ENTER),
+    ("Escape", ESCAPE),
+
+    ("F0", keycode(ncurses::KEY_F0)),
+    ("F1", keycode(ncurses::KEY_F1)),
+    ("F2", keycode(ncurses::KEY_F2)),
+    ("F3", keycode(ncurses::KEY_F3)),
+    ("F4", keycode(ncurses::KEY_F4)),
+    ("F5", keycode(ncurses::KEY_F5)),
+    ("F6", keycode(ncurses::KEY_F6)),
+    ("F7", keycode(ncurses::KEY_F7)),
+    ("F8", keycode(ncurses::KEY_F8)),
+    ("F9", keycode(ncurses::KEY_F9)),
+    ("F10", keycode(ncurses::KEY_F10)),
+    ("F11", keycode(ncurses::KEY_F11)),
+    ("F12", keycode(ncurses::KEY_F12)),
+    ("F13", keycode(ncurses::KEY_F13)),
+    ("F14", keycode(ncurses::KEY_F14)),
+    ("F15", keycode(ncurses::KEY_F15)),
 
+    ("Insert", keycode(ncurses::KEY_IC)),            /* insert-character key */
+    ("PageUp", keycode(ncurses::KEY_PPAGE)),         /* previous-page key */
+    ("PageDown", keycode(ncurses::KEY_NPAGE)),       /* next-page key */
+    ("PrintScreen", keycode(ncurses::KEY_PRINT)),    /* print key */
+
+    ("Up", keycode(ncurses::KEY_UP)),
+    ("Down", keycode(ncurses::KEY_DOWN)),
+    ("Left", keycode(ncurses::KEY_LEFT)),
+    ("Right", keycode(ncurses::KEY_RIGHT)),
+    ("Home", keycode(ncurses::KEY_HOME)),
+    ("End", keycode(ncurses::KEY_END)),
+
+    /* shifted keys ncurses has its own codes for */
+    ("S-Tab", keycode(ncurses::KEY_BTAB)),
+    ("S-Delete", keycode(ncurses::KEY_SDC)),
+    ("S-Insert", keycode(ncurses::KEY_SIC)),
+    ("S-Up", keycode(ncurses::KEY_SR)),
+    ("S-Down", keycode(ncurses::KEY_SF)),
+    ("S-Left", keycode(ncurses::KEY_SLEFT)),
+    ("S-Right", keycode(ncurses::KEY_SRIGHT)),
+    ("S-Home", keycode(ncurses::KEY_SHOME)),
+    ("S-End", keycode(ncurses::KEY_SEND)),
+];
+
+fn single_char(keycode: &str) -> Option<char>
+{
+    let mut chars = keycode.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Some(ch),
+        _ => None,
+    }
+}
+
+/* C-x, the terminal clears bits 6 and 7 of the character */
+fn ctrl_key(keycode: &str) -> Option<i32>
+{
+    if keycode == "Space" {
+        return Some(0);
+    }
+    match single_char(keycode)? {
+        '?' => Some(BACKSPACE),
+        ch @ 'a' ..= 'z' => Some(ch as i32 - 'a' as i32 + 1),
+        ch @ '@' ..= '_' => Some(ch as i32 - '@' as i32),
+        _ => None,
+    }
+}
+
+/* a single key without the Alt prefix: a character,
+ * a key name, C-x or S-x */
 pub fn key_to_i32(keycode: &str) -> Option<i32>
 {
-    if keycode.len() == 1 {
-        for ch in keycode.chars() {
-            if ch.is_ascii() {
-                return Some(ch as i32);
+    if let Some(ch) = single_char(keycode) {
+        return Some(ch as i32);
+    }
+    if let Some(s) = keycode.strip_prefix("C-") {
+        return ctrl_key(s);
+    }
+    if let Some(s) = keycode.strip_prefix("S-") {
+        if let Some(ch) = single_char(s) {
+            if ch.is_alphabetic() {
+                let mut upper = ch.to_uppercase();
+                return match (upper.next(), upper.next()) {
+                        (Some(ch), None) => Some(ch as i32),
+                        _ => None,
+                    };
             }
         }
-        return None;
-    } else {
-        match keycode {
-            "Tab" => Some(TAB),
-            "Space" => Some(' ' as i32),
-            "Backspace" => Some(BACKSPACE),
-            "Delete" => Some(ncurses::KEY_DC),
-            "Enter" => Some(ENTER),
-            "Escape" => Some(ESCAPE),
-
-
-            "F0" => Some(ncurses::KEY_F0),
-            "F1" => Some(ncurses::KEY_F1),
-            "F2" => Some(ncurses::KEY_F2),
-            "F3" => Some(ncurses::KEY_F3),
-            "F4" => Some(ncurses::KEY_F4),
-            "F5" => Some(ncurses::KEY_F5),
-            "F6" => Some(ncurses::KEY_F6),
-            "F7" => Some(ncurses::KEY_F7),
-            "F8" => Some(ncurses::KEY_F8),
-            "F9" => Some(ncurses::KEY_F9),
-            "F10" => Some(ncurses::KEY_F10),
-            "F11" => Some(ncurses::KEY_F11),
-            "F12" => Some(ncurses::KEY_F12),
-            "F13" => Some(ncurses::KEY_F13),
-            "F14" => Some(ncurses::KEY_F14),
-            "F15" => Some(ncurses::KEY_F15),
-
-            "Insert" => Some(ncurses::KEY_IC),           /* insert-character key */
-            "PageUp" => Some(ncurses::KEY_PPAGE),        /* next-page key */
-            "PageDown" => Some(ncurses::KEY_NPAGE),      /* previous-page key */
-            "PrintScreen" => Some(ncurses::KEY_PRINT),    /* print key */
-
-            "Up" => Some(ncurses::KEY_UP),
-            "Down" => Some(ncurses::KEY_DOWN),
-            "Left" => Some(ncurses::KEY_LEFT),
-            "Right" => Some(ncurses::KEY_RIGHT),
-            "Home" => Some(ncurses::KEY_HOME),
-            "End" => Some(ncurses::KEY_END),
-            _ => None,
+    }
+    KEY_NAMES.iter().find(|(name, _)| *name == keycode).map(|(_, key)| *key)
+}
+
+/* terminals send Alt as Escape followed by the key,
+ * so M-x becomes the two keys Escape and x */
+pub fn str_to_keys(keycode: &str) -> Result<Vec<i32>, String>
+{
+    let mut keys: Vec<i32> = Vec::new();
+    let mut keycode = keycode;
+    while let Some(s) = keycode.strip_prefix("M-").or_else(|| keycode.strip_prefix("A-")) {
+        if s.is_empty() {
+            break;
         }
+        keys.push(ESCAPE);
+        keycode = s;
+    }
+    match key_to_i32(keycode) {
+        Some(s) => {
+            keys.push(s);
+            Ok(keys)
+        },
+        None => Err(format!("Unknown key: {}", keycode)),
+    }
+}
+
+/* inverse of key_to_i32, for showing keybindings */
+pub fn key_to_string(key: i32) -> String
+{
+    if let Some((name, _)) = KEY_NAMES.iter().find(|(_, k)| *k == key) {
+        return name.to_string();
+    }
+    if key == 0 {
+        return String::from("C-Space");
+    }
+    if key > 0 && key < 0x20 {
+        return format!("C-{}", (key as u8 + b'a' - 1) as char);
+    }
+    match std::char::from_u32(key as u32) {
+        Some(ch) if !ch.is_control() => ch.to_string(),
+        _ => format!("<{:#x}>", key),
     }
 }
@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    #[test]
+    fn single_keys() {
+        assert_eq!(Some('a' as i32), key_to_i32("a"));
+        assert_eq!(Some('ö' as i32), key_to_i32("ö"));
+        assert_eq!(Some(TAB), key_to_i32("Tab"));
+        assert_eq!(None, key_to_i32("NotAKey"));
+    }
+    #[test]
+    fn modifiers() {
+        assert_eq!(Some(0x18), key_to_i32("C-x"));
+        assert_eq!(Some(0x18), key_to_i32("C-X"));
+        assert_eq!(Some(ESCAPE), key_to_i32("C-["));
+        assert_eq!(Some('F' as i32), key_to_i32("S-f"));
+        assert_eq!(Some('Ö' as i32), key_to_i32("S-ö"));
+        assert_eq!(Some(keycode(ncurses::KEY_BTAB)), key_to_i32("S-Tab"));
+        assert_eq!(Ok(vec![ESCAPE, 'f' as i32]), str_to_keys("M-f"));
+        assert_eq!(Ok(vec![ESCAPE, 0x18]), str_to_keys("M-C-x"));
+        assert!(str_to_keys("C-Tab").is_err());
+        assert!(str_to_keys("M-").is_err());
+    }
+    #[test]
+    fn keys_apart_from_chars() {
+        assert_ne!(key_to_i32("Right"), key_to_i32("ą"));
+        assert_eq!(key_to_i32("Down"), Some(wch_to_key(ncurses::WchResult::KeyCode(ncurses::KEY_DOWN))));
+        assert_eq!(key_to_i32("Ă"), Some(wch_to_key(ncurses::WchResult::Char('Ă' as u32))));
+    }
+    #[test]
+    fn key_names() {
+        for key in &["a", "ö", "Tab", "C-x", "S-Tab", "PageDown"] {
+            assert_eq!(*key, key_to_string(key_to_i32(key).unwrap()));
+        }
+    }
+    #[test]
+    fn flatten_errors() {
+        let raw: JoshutoRawKeymap = toml::from_str(r#"
+            [[mapcommand]]
+            keys = [ "d", "d" ]
+            command = "quit"
+
+            [[mapcommand]]
+            keys = [ "d" ]
+            command = "quit"
+
+            [[mapcommand]]
+            keys = [ "x" ]
+            command = "no_such_command"
+            "#).unwrap();
+        let mut errors = Vec::new();
+        let keymap = raw.flatten(&HashMap::new(), &mut errors);
+        assert_eq!(1, keymap.keymaps.len());
+        assert_eq!(2, errors.len());
+        assert_eq!(Some(String::from("d")), errors[0].key);
+        assert_eq!(Some(String::from("x")), errors[1].key);
+    }
+    #[test]
+    fn flattened_bindings() {
+        let raw: JoshutoRawKeymap = toml::from_str(r#"
+            [[mapcommand]]
+            keys = [ "z", "h" ]
+            command = "toggle_hidden"
+
+            [[mapcommand]]
+            keys = [ "q" ]
+            command = "quit"
+            "#).unwrap();
+        let mut errors = Vec::new();
+        let keymap = raw.flatten(&HashMap::new(), &mut errors);
+        assert_eq!(vec![(String::from("q"), String::from("quit")),
+                (String::from("z h"), String::from("toggle_hidden"))],
+                keymap.bindings());
+    }
+    #[test]
+    fn merge_layers() {
+        let base: JoshutoRawKeymap = toml::from_str(r#"
+            [[mapcommand]]
+            keys = [ "d", "d" ]
+            command = "cut_files"
+
+            [[mapcommand]]
+            keys = [ "z", "h" ]
+            command = "toggle_hidden"
+            "#).unwrap();
+        let layer: JoshutoRawKeymap = toml::from_str(r#"
+            [[mapcommand]]
+            keys = [ "d" ]
+            command = "delete_files"
+
+            [[mapcommand]]
+            keys = [ "z", "z" ]
+            command = "quit"
+            "#).unwrap();
+        let mut errors = Vec::new();
+        let mut keymap = base.flatten(&HashMap::new(), &mut errors);
+        merge_keymaps(&mut keymap.keymaps, layer.flatten(&HashMap::new(), &mut errors).keymaps);
+        assert!(errors.is_empty());
+        assert_eq!(vec![(String::from("d"), String::from("delete_files")),
+                (String::from("z h"), String::from("toggle_hidden")),
+                (String::from("z z"), String::from("quit"))],
+                keymap.bindings());
+    }
+}
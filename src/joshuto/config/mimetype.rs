@@ -2,9 +2,12 @@ extern crate toml;
 extern crate xdg;
 
 use std::fmt;
-use std::fs;
 use std::collections::HashMap;
-use std::process;
+
+use joshuto::config::parse;
+use joshuto::config::ConfigError;
+
+pub const DEFAULT_MIMETYPE: &str = include_str!("../../../config/mimetype.toml");
 
 #[derive(Debug, Deserialize)]
 pub struct JoshutoMimetypeEntry {
@@ -45,7 +48,6 @@ pub struct JoshutoRawMimetype {
 }
 
 impl JoshutoRawMimetype {
-    #[allow(dead_code)]
     pub fn new() -> Self
     {
         JoshutoRawMimetype {
@@ -54,6 +56,17 @@ impl JoshutoRawMimetype {
         }
     }
 
+    /* programs for a mimetype or extension in layer replace the ones in self */
+    pub fn merge(&mut self, layer: JoshutoRawMimetype, _errors: &mut Vec<ConfigError>)
+    {
+        if let Some(s) = layer.mimetypes {
+            self.mimetypes.get_or_insert_with(HashMap::new).extend(s);
+        }
+        if let Some(s) = layer.extensions {
+            self.extensions.get_or_insert_with(HashMap::new).extend(s);
+        }
+    }
+
     pub fn flatten(self) -> JoshutoMimetype
     {
         let mimetypes = self.mimetypes.unwrap_or(HashMap::new());
@@ -82,45 +95,9 @@ impl JoshutoMimetype {
         }
     }
 
-    fn read_config() -> Option<JoshutoRawMimetype>
+    pub fn load(errors: &mut Vec<ConfigError>) -> Self
     {
-        match xdg::BaseDirectories::with_profile(::PROGRAM_NAME, "") {
-            Ok(dirs) => {
-                let config_path = dirs.find_config_file(::MIMETYPE_FILE)?;
-                match fs::read_to_string(&config_path) {
-                    Ok(config_contents) => {
-                        match toml::from_str(&config_contents) {
-                            Ok(config) => {
-                                Some(config)
-                            },
-                            Err(e) => {
-                                eprintln!("Error parsing mimetype file: {}", e);
-                                process::exit(1);
-                            },
-                        }
-                    },
-                    Err(e) => {
-                        eprintln!("{}", e);
-                        None
-                    },
-                }
-            },
-            Err(e) => {
-                eprintln!("{}", e);
-                None
-            },
-        }
-    }
-
-    pub fn get_config() -> Self
-    {
-        match Self::read_config() {
-            Some(config) => {
-                config.flatten()
-            }
-            None => {
-                Self::new()
-            }
-        }
+        parse::parse_config_layers(::MIMETYPE_FILE, DEFAULT_MIMETYPE, errors,
+                JoshutoRawMimetype::new(), JoshutoRawMimetype::merge).flatten()
     }
 }
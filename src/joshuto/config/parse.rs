@@ -0,0 +1,277 @@
+extern crate serde;
+extern crate toml;
+extern crate xdg;
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path;
+
+use joshuto::command;
+use joshuto::config::config;
+use joshuto::config::keymap;
+use joshuto::config::mimetype;
+use joshuto::config::theme;
+use joshuto::config::JoshutoConfig;
+use joshuto::config::JoshutoKeymap;
+use joshuto::config::JoshutoMimetype;
+use joshuto::config::JoshutoTheme;
+
+/* a problem found in a config file */
+#[derive(Debug)]
+pub struct ConfigError {
+    pub file: Option<path::PathBuf>,
+    pub line: Option<usize>,
+    pub key: Option<String>,
+    pub error: String,
+    /* byte offset into the file, turned into a line once the file is known */
+    offset: Option<usize>,
+}
+
+impl ConfigError {
+    pub fn new(key: Option<String>, error: String) -> Self
+    {
+        ConfigError {
+            file: None,
+            line: None,
+            key,
+            error,
+            offset: None,
+        }
+    }
+
+    pub fn at(offset: usize, key: Option<String>, error: String) -> Self
+    {
+        ConfigError {
+            file: None,
+            line: None,
+            key,
+            error,
+            offset: Some(offset),
+        }
+    }
+
+    fn locate(&mut self, file: &path::Path, contents: &str)
+    {
+        self.file = Some(file.to_path_buf());
+        if let Some(offset) = self.offset {
+            let offset = offset.min(contents.len());
+            self.line = Some(contents.as_bytes()[..offset].iter()
+                    .filter(|b| **b == b'\n').count() + 1);
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        if let Some(s) = self.file.as_ref() {
+            write!(f, "{}:", s.to_string_lossy())?;
+            if let Some(line) = self.line {
+                write!(f, "{}:", line)?;
+            }
+            f.write_str(" ")?;
+        }
+        if let Some(s) = self.key.as_ref() {
+            write!(f, "{}: ", s)?;
+        }
+        f.write_str(self.error.as_str())
+    }
+}
+
+/* config files for file_name, lowest priority first:
+ * the system config directories, the user's config directory,
+ * then the directory given by $JOSHUTO_CONFIG_DIR or --config-dir */
+fn config_paths(file_name: &str) -> Result<Vec<path::PathBuf>, ConfigError>
+{
+    let dirs = match xdg::BaseDirectories::with_profile(::PROGRAM_NAME, "") {
+            Ok(s) => s,
+            Err(e) => return Err(ConfigError::new(None, e.to_string())),
+        };
+    let mut paths: Vec<path::PathBuf> = dirs.find_config_files(file_name).collect();
+    paths.reverse();
+
+    if let Some(dir) = env::var_os(::CONFIG_DIR_ENV) {
+        let config_path = path::PathBuf::from(dir).join(file_name);
+        if config_path.exists() && !paths.contains(&config_path) {
+            paths.push(config_path);
+        }
+    }
+    Ok(paths)
+}
+
+/* config_paths skips files missing from $JOSHUTO_CONFIG_DIR or --config-dir,
+ * but the directory itself being missing is most likely a typo */
+pub fn check_config_dir(errors: &mut Vec<ConfigError>)
+{
+    if let Some(dir) = env::var_os(::CONFIG_DIR_ENV) {
+        let dir = path::PathBuf::from(dir);
+        if !dir.is_dir() {
+            let mut err = ConfigError::new(None, String::from("config directory not found"));
+            err.file = Some(dir);
+            errors.push(err);
+        }
+    }
+}
+
+/* parse each layer of file_name, starting from the built-in default,
+ * and merge it on top of the layers before it.
+ * a layer that can't be read or parsed is skipped and reported */
+pub fn parse_config_layers<T, S, F>(file_name: &str, default: &str,
+        errors: &mut Vec<ConfigError>, mut config: S, mut merge: F) -> S
+    where T: serde::de::DeserializeOwned,
+          F: FnMut(&mut S, T, &mut Vec<ConfigError>)
+{
+    let mut layers: Vec<(path::PathBuf, String)> = vec![
+            (path::PathBuf::from(format!("(built-in) {}", file_name)), default.to_string())
+        ];
+    match config_paths(file_name) {
+        Ok(paths) => {
+            for config_path in paths {
+                match fs::read_to_string(&config_path) {
+                    Ok(s) => layers.push((config_path, s)),
+                    Err(e) => {
+                        let mut err = ConfigError::new(None, e.to_string());
+                        err.locate(&config_path, "");
+                        errors.push(err);
+                    },
+                }
+            }
+        },
+        Err(e) => errors.push(e),
+    }
+
+    for (config_path, contents) in layers {
+        match toml::from_str::<T>(&contents) {
+            Ok(layer) => {
+                let start = errors.len();
+                merge(&mut config, layer, errors);
+                for e in errors[start..].iter_mut() {
+                    e.locate(&config_path, &contents);
+                }
+            },
+            Err(e) => {
+                let mut err = ConfigError::new(None, e.to_string());
+                err.locate(&config_path, &contents);
+                if let Some((line, _)) = e.line_col() {
+                    err.line = Some(line + 1);
+                }
+                errors.push(err);
+            },
+        }
+    }
+    config
+}
+
+/* a command line read from joshuto.rc */
+#[derive(Clone, Debug)]
+pub struct RcLine {
+    pub file: path::PathBuf,
+    pub line: usize,
+    pub command: String,
+}
+
+/* command lines in every joshuto.rc, lowest priority layer first.
+ * blank lines and lines starting with # are skipped */
+pub fn read_rc_files(errors: &mut Vec<ConfigError>) -> Vec<RcLine>
+{
+    let paths = match config_paths(::RC_FILE) {
+            Ok(s) => s,
+            Err(e) => {
+                errors.push(e);
+                return Vec::new();
+            },
+        };
+
+    let mut rc_lines: Vec<RcLine> = Vec::new();
+    for rc_path in paths {
+        match fs::read_to_string(&rc_path) {
+            Ok(contents) => {
+                for (i, line) in contents.lines().enumerate() {
+                    let line = line.trim();
+                    if line.is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    rc_lines.push(RcLine {
+                        file: rc_path.clone(),
+                        line: i + 1,
+                        command: line.to_string(),
+                    });
+                }
+            },
+            Err(e) => {
+                let mut err = ConfigError::new(None, e.to_string());
+                err.locate(&rc_path, "");
+                errors.push(err);
+            },
+        }
+    }
+    rc_lines
+}
+
+fn check_command_line(line: &str, aliases: &HashMap<String, String>) -> Result<(), String>
+{
+    let args: Vec<String> = command::split_shell_style(line).iter()
+            .map(|s| s.to_string()).collect();
+    if args.is_empty() {
+        return Err(String::from("empty command"));
+    }
+    match command::from_args(args[0].as_str(), &args[1..], aliases) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/* load every config file, returning all the problems found */
+pub fn check_config() -> Vec<ConfigError>
+{
+    let mut errors: Vec<ConfigError> = Vec::new();
+
+    check_config_dir(&mut errors);
+    let config = JoshutoConfig::load(&mut errors);
+    JoshutoKeymap::load(&config.aliases, &mut errors);
+    JoshutoTheme::load(&mut errors);
+    JoshutoMimetype::load(&mut errors);
+
+    for line in &config.startup {
+        if let Err(e) = check_command_line(line, &config.aliases) {
+            errors.push(ConfigError::new(Some(String::from("startup")),
+                    format!("{}: {}", line, e)));
+        }
+    }
+    for rc_line in read_rc_files(&mut errors) {
+        if let Err(e) = check_command_line(rc_line.command.as_str(), &config.aliases) {
+            let mut err = ConfigError::new(None, e);
+            err.file = Some(rc_line.file);
+            err.line = Some(rc_line.line);
+            errors.push(err);
+        }
+    }
+    errors
+}
+
+/* write the built-in config files into dir, without replacing existing files */
+pub fn dump_default_config(dir: &path::Path) -> io::Result<()>
+{
+    let defaults = [
+        (::CONFIG_FILE, config::DEFAULT_CONFIG),
+        (::KEYMAP_FILE, keymap::DEFAULT_KEYMAP),
+        (::MIMETYPE_FILE, mimetype::DEFAULT_MIMETYPE),
+        (::THEME_FILE, theme::DEFAULT_THEME),
+    ];
+
+    fs::create_dir_all(dir)?;
+    for (file_name, _) in defaults.iter() {
+        let file_path = dir.join(file_name);
+        if file_path.exists() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists,
+                    format!("{} already exists", file_path.to_string_lossy())));
+        }
+    }
+    for (file_name, contents) in defaults.iter() {
+        fs::write(dir.join(file_name), contents)?;
+    }
+    Ok(())
+}
@@ -2,8 +2,11 @@ extern crate toml;
 extern crate xdg;
 
 use std::collections::HashMap;
-use std::fs;
-use std::process;
+
+use joshuto::config::parse;
+use joshuto::config::ConfigError;
+
+pub const DEFAULT_THEME: &str = include_str!("../../../config/theme.toml");
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct JoshutoColorPair {
@@ -23,9 +26,9 @@ impl JoshutoColorPair {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize)]
 pub struct JoshutoRawColorTheme {
-    pub colorpair: i16,
+    pub colorpair: toml::Spanned<i16>,
     pub bold: Option<bool>,
     pub underline: Option<bool>,
     pub prefix: Option<String>,
@@ -36,7 +39,7 @@ impl JoshutoRawColorTheme {
     pub fn flatten(self) -> JoshutoColorTheme
     {
         JoshutoColorTheme {
-            colorpair: self.colorpair,
+            colorpair: self.colorpair.into_inner(),
             bold: self.bold.unwrap_or(false),
             underline: self.underline.unwrap_or(false),
             prefix: self.prefix,
@@ -58,70 +61,103 @@ pub struct JoshutoRawTheme {
 }
 
 impl JoshutoRawTheme {
-    pub fn flatten(self) -> JoshutoTheme
+    pub fn new() -> Self
+    {
+        JoshutoRawTheme {
+            colorpair: None,
+            selection: None,
+            executable: None,
+            regular: None,
+            directory: None,
+            link: None,
+            socket: None,
+            ext: None,
+        }
+    }
+
+    /* colorpairs in layer replace the ones in self with the same id,
+     * other values set in layer replace the ones in self */
+    pub fn merge(&mut self, layer: JoshutoRawTheme, errors: &mut Vec<ConfigError>)
     {
-        let colorpair = match self.colorpair {
+        if let Some(pairs) = layer.colorpair {
+            let colorpair = self.colorpair.get_or_insert_with(Vec::new);
+            for pair in pairs {
+                colorpair.retain(|s| s.id != pair.id);
+                colorpair.push(pair);
+            }
+        }
+
+        /* colorpair 0 is the terminal's default colors */
+        let colorpair: &[JoshutoColorPair] = match self.colorpair.as_ref() {
                 Some(s) => s,
-                None => {
-                    let mut colorpair: Vec<JoshutoColorPair> = Vec::with_capacity(10);
-                    colorpair.push(JoshutoColorPair::new(2, 2, -1));
-                    colorpair.push(JoshutoColorPair::new(3, 3, -1));
-                    colorpair.push(JoshutoColorPair::new(4, 4, -1));
-                    colorpair.push(JoshutoColorPair::new(5, 5, -1));
-                    colorpair.push(JoshutoColorPair::new(6, 6, -1));
-                    colorpair
-                }
+                None => &[],
             };
+        let mut check_color = |key: &str, raw: Option<&JoshutoRawColorTheme>| {
+            if let Some(s) = raw {
+                let id = *s.colorpair.get_ref();
+                if id != 0 && !colorpair.iter().any(|pair| pair.id == id) {
+                    errors.push(ConfigError::at(s.colorpair.start(),
+                            Some(format!("{}.colorpair", key)),
+                            format!("Undefined colorpair: {}", id)));
+                }
+            }
+        };
+        check_color("selection", layer.selection.as_ref());
+        check_color("executable", layer.executable.as_ref());
+        check_color("regular", layer.regular.as_ref());
+        check_color("directory", layer.directory.as_ref());
+        check_color("link", layer.link.as_ref());
+        check_color("socket", layer.socket.as_ref());
+        if let Some(ext) = layer.ext.as_ref() {
+            for (k, v) in ext {
+                check_color(format!("ext.{}", k).as_str(), Some(v));
+            }
+        }
 
-        let selection = self.selection.unwrap_or(JoshutoRawColorTheme {
-            colorpair: 3,
-            bold: Some(true),
-            underline: None,
-            prefix: None,
-            prefixsize: None,
-            });
-
-        let executable = self.executable.unwrap_or(JoshutoRawColorTheme {
-            colorpair: 2,
-            bold: Some(true),
-            underline: None,
-            prefix: None,
-            prefixsize: None,
-            });
+        if layer.selection.is_some() {
+            self.selection = layer.selection;
+        }
+        if layer.executable.is_some() {
+            self.executable = layer.executable;
+        }
+        if layer.regular.is_some() {
+            self.regular = layer.regular;
+        }
+        if layer.directory.is_some() {
+            self.directory = layer.directory;
+        }
+        if layer.link.is_some() {
+            self.link = layer.link;
+        }
+        if layer.socket.is_some() {
+            self.socket = layer.socket;
+        }
+        if let Some(s) = layer.ext {
+            self.ext.get_or_insert_with(HashMap::new).extend(s);
+        }
+    }
 
-        let regular = self.regular.unwrap_or(JoshutoRawColorTheme {
-            colorpair: 0,
-            bold: None,
-            underline: None,
-            prefix: None,
-            prefixsize: None,
-            });
+    pub fn flatten(self) -> JoshutoTheme
+    {
+        let defaults = JoshutoTheme::new();
 
-        let directory = self.directory.unwrap_or(JoshutoRawColorTheme {
-            colorpair: 4,
-            bold: Some(true),
-            underline: None,
-            prefix: None,
-            prefixsize: None,
-            });
+        let colorpair = self.colorpair.unwrap_or(defaults.colorpair);
 
-        let link = self.link.unwrap_or(JoshutoRawColorTheme {
-            colorpair: 6,
-            bold: Some(true),
-            underline: None,
-            prefix: None,
-            prefixsize: None,
-            });
+        let flatten_color = |raw: Option<JoshutoRawColorTheme>, default: JoshutoColorTheme| {
+            match raw {
+                Some(s) => s.flatten(),
+                None => default,
+            }
+        };
 
-        let socket = self.socket.unwrap_or(JoshutoRawColorTheme {
-            colorpair: 6,
-            bold: Some(true),
-            underline: None,
-            prefix: None,
-            prefixsize: None,
-            });
+        let selection = flatten_color(self.selection, defaults.selection);
+        let executable = flatten_color(self.executable, defaults.executable);
+        let regular = flatten_color(self.regular, defaults.regular);
+        let directory = flatten_color(self.directory, defaults.directory);
+        let link = flatten_color(self.link, defaults.link);
+        let socket = flatten_color(self.socket, defaults.socket);
 
-        let mut extraw = self.ext.unwrap_or(HashMap::new());
+        let mut extraw = self.ext.unwrap_or_default();
         let mut ext: HashMap<String, JoshutoColorTheme> = HashMap::new();
         for (k, v) in extraw.drain() {
             ext.insert(k, v.flatten());
@@ -129,12 +165,12 @@ impl JoshutoRawTheme {
 
         JoshutoTheme {
             colorpair,
-            regular: regular.flatten(),
-            directory: directory.flatten(),
-            selection: selection.flatten(),
-            executable: executable.flatten(),
-            link: link.flatten(),
-            socket: socket.flatten(),
+            regular,
+            directory,
+            selection,
+            executable,
+            link,
+            socket,
             ext,
         }
     }
@@ -232,45 +268,9 @@ impl JoshutoTheme {
 
     }
 
-    fn read_config() -> Option<JoshutoRawTheme>
+    pub fn load(errors: &mut Vec<ConfigError>) -> Self
     {
-        match xdg::BaseDirectories::with_profile(::PROGRAM_NAME, "") {
-            Ok(dirs) => {
-                let config_path = dirs.find_config_file(::THEME_FILE)?;
-                match fs::read_to_string(&config_path) {
-                    Ok(config_contents) => {
-                        match toml::from_str(&config_contents) {
-                            Ok(config) => {
-                                Some(config)
-                            },
-                            Err(e) => {
-                                eprintln!("Error parsing theme file: {}", e);
-                                process::exit(1);
-                            },
-                        }
-                    },
-                    Err(e) => {
-                        eprintln!("{}", e);
-                        None
-                    },
-                }
-            },
-            Err(e) => {
-                eprintln!("{}", e);
-                None
-            },
-        }
-    }
-
-    pub fn get_config() -> Self
-    {
-        match Self::read_config() {
-            Some(config) => {
-                config.flatten()
-            }
-            None => {
-                Self::new()
-            }
-        }
+        parse::parse_config_layers(::THEME_FILE, DEFAULT_THEME, errors,
+                JoshutoRawTheme::new(), JoshutoRawTheme::merge).flatten()
     }
 }
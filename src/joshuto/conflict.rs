@@ -0,0 +1,161 @@
+use std::ffi;
+use std::fs;
+use std::path;
+
+use joshuto::register::FileOp;
+
+#[cfg(test)]
+mod test;
+
+pub const DEFAULT_RENAME_PATTERN: &str = "{name} ({n}){ext}";
+
+/* what to do with a pasted file whose name is taken in the destination */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConflictPolicy {
+    Ask,
+    Overwrite,
+    Skip,
+    Rename,
+}
+
+impl ConflictPolicy {
+    pub fn parse(s: &str) -> Option<Self>
+    {
+        match s {
+            "ask" => Some(ConflictPolicy::Ask),
+            "overwrite" => Some(ConflictPolicy::Overwrite),
+            "skip" => Some(ConflictPolicy::Skip),
+            "rename" => Some(ConflictPolicy::Rename),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str
+    {
+        match self {
+            ConflictPolicy::Ask => "ask",
+            ConflictPolicy::Overwrite => "overwrite",
+            ConflictPolicy::Skip => "skip",
+            ConflictPolicy::Rename => "rename",
+        }
+    }
+}
+
+/* a file to paste and where it goes */
+#[derive(Clone, Debug, PartialEq)]
+pub struct PasteItem {
+    pub source: path::PathBuf,
+    pub destination: path::PathBuf,
+    /* replace what is at destination */
+    pub overwrite: bool,
+}
+
+/* also true for broken symlinks */
+pub fn path_exists(path: &path::Path) -> bool
+{
+    fs::symlink_metadata(path).is_ok()
+}
+
+/* file_name put through pattern: {name} is the name without its extension,
+ * {ext} the extension with its dot and {n} the number n */
+pub fn rename_with_pattern(file_name: &ffi::OsStr, pattern: &str, n: usize) -> ffi::OsString
+{
+    let file_path = path::Path::new(file_name);
+    let name = file_path.file_stem().unwrap_or(file_name);
+    let ext: ffi::OsString = match file_path.extension() {
+            Some(s) => {
+                let mut ext = ffi::OsString::from(".");
+                ext.push(s);
+                ext
+            },
+            None => ffi::OsString::new(),
+        };
+
+    let mut renamed = ffi::OsString::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        renamed.push(&rest[..start]);
+        rest = &rest[start..];
+        if rest.starts_with("{name}") {
+            renamed.push(name);
+            rest = &rest["{name}".len()..];
+        } else if rest.starts_with("{ext}") {
+            renamed.push(&ext);
+            rest = &rest["{ext}".len()..];
+        } else if rest.starts_with("{n}") {
+            renamed.push(n.to_string());
+            rest = &rest["{n}".len()..];
+        } else {
+            renamed.push("{");
+            rest = &rest[1..];
+        }
+    }
+    renamed.push(rest);
+    renamed
+}
+
+/* the first name given by pattern that is free in dir and not in taken */
+pub fn unused_name(dir: &path::Path, file_name: &ffi::OsStr, pattern: &str,
+        taken: &[path::PathBuf]) -> path::PathBuf
+{
+    let mut n: usize = 1;
+    loop {
+        let destination = dir.join(rename_with_pattern(file_name, pattern, n));
+        if !path_exists(&destination) && !taken.contains(&destination) {
+            return destination;
+        }
+        n += 1;
+    }
+}
+
+/* where each of paths goes when pasted into dir. ask is called for each
+ * conflict while policy is Ask, and returns the policy for that file and
+ * whether it applies to the rest, or None to cancel the paste */
+pub fn plan_paste<F>(paths: &[path::PathBuf], dir: &path::Path, operation: FileOp,
+        mut policy: ConflictPolicy, pattern: &str, mut ask: F) -> Option<Vec<PasteItem>>
+    where F: FnMut(&path::Path, &path::Path) -> Option<(ConflictPolicy, bool)>
+{
+    let mut items: Vec<PasteItem> = Vec::with_capacity(paths.len());
+    let mut taken: Vec<path::PathBuf> = Vec::with_capacity(paths.len());
+    for path in paths {
+        let file_name = match path.file_name() {
+                Some(s) => s,
+                None => continue,
+            };
+        let mut destination = dir.join(file_name);
+        let mut overwrite = false;
+
+        if destination == *path {
+            /* pasting into the directory the files are already in */
+            match operation {
+                FileOp::Copy => destination = unused_name(dir, file_name, pattern, &taken),
+                FileOp::Cut => continue,
+            }
+        } else if path_exists(&destination) || taken.contains(&destination) {
+            let curr_policy = match policy {
+                    ConflictPolicy::Ask => {
+                        let (answer, apply_all) = ask(path, &destination)?;
+                        if apply_all {
+                            policy = answer;
+                        }
+                        answer
+                    },
+                    s => s,
+                };
+            match curr_policy {
+                ConflictPolicy::Skip | ConflictPolicy::Ask => continue,
+                ConflictPolicy::Overwrite => overwrite = true,
+                ConflictPolicy::Rename => {
+                    destination = unused_name(dir, file_name, pattern, &taken);
+                },
+            }
+        }
+        taken.push(destination.clone());
+        items.push(PasteItem {
+            source: path.clone(),
+            destination,
+            overwrite,
+        });
+    }
+    Some(items)
+}
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use joshuto::test_helpers::temp_dir;
+    use std::ffi::OsStr;
+    use std::path::PathBuf;
+
+    #[test]
+    fn rename_patterns() {
+        assert_eq!("notes (1).txt",
+            rename_with_pattern(OsStr::new("notes.txt"), DEFAULT_RENAME_PATTERN, 1));
+        assert_eq!("archive.tar (2).gz",
+            rename_with_pattern(OsStr::new("archive.tar.gz"), DEFAULT_RENAME_PATTERN, 2));
+        assert_eq!(".bashrc (3)",
+            rename_with_pattern(OsStr::new(".bashrc"), DEFAULT_RENAME_PATTERN, 3));
+        assert_eq!("copy_4_of_a.b {x}",
+            rename_with_pattern(OsStr::new("a.b"), "copy_{n}_of_{name}{ext} {x}", 4));
+    }
+
+    #[test]
+    fn plan_conflicts() {
+        let dir = temp_dir("plan");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("dst")).unwrap();
+        for name in &["a.txt", "b", "c"] {
+            fs::write(dir.join("src").join(name), "").unwrap();
+        }
+        fs::write(dir.join("dst").join("a.txt"), "").unwrap();
+        fs::write(dir.join("dst").join("a (1).txt"), "").unwrap();
+        fs::write(dir.join("dst").join("b"), "").unwrap();
+        let paths: Vec<PathBuf> = ["a.txt", "b", "c"].iter()
+            .map(|s| dir.join("src").join(s)).collect();
+        let dst = dir.join("dst");
+
+        let items = plan_paste(&paths, &dst, FileOp::Copy, ConflictPolicy::Rename,
+                DEFAULT_RENAME_PATTERN, |_, _| None).unwrap();
+        let destinations: Vec<PathBuf> = items.iter().map(|s| s.destination.clone()).collect();
+        assert_eq!(vec![dst.join("a (2).txt"), dst.join("b (1)"), dst.join("c")], destinations);
+
+        /* the answer to the first conflict applies to the second */
+        let mut asked = 0;
+        let items = plan_paste(&paths, &dst, FileOp::Cut, ConflictPolicy::Ask,
+                DEFAULT_RENAME_PATTERN, |_, _| {
+                    asked += 1;
+                    Some((ConflictPolicy::Overwrite, true))
+                }).unwrap();
+        assert_eq!(1, asked);
+        assert_eq!(3, items.len());
+        assert!(items[0].overwrite && items[1].overwrite && !items[2].overwrite);
+
+        let items = plan_paste(&paths, &dst, FileOp::Copy, ConflictPolicy::Skip,
+                DEFAULT_RENAME_PATTERN, |_, _| None).unwrap();
+        assert_eq!(vec![dst.join("c")],
+            items.iter().map(|s| s.destination.clone()).collect::<Vec<PathBuf>>());
+        assert!(plan_paste(&paths, &dst, FileOp::Copy, ConflictPolicy::Ask,
+                DEFAULT_RENAME_PATTERN, |_, _| None).is_none());
+
+        /* pasting where the files already are */
+        let src = dir.join("src");
+        let items = plan_paste(&paths[2..], &src, FileOp::Copy, ConflictPolicy::Overwrite,
+                DEFAULT_RENAME_PATTERN, |_, _| None).unwrap();
+        assert_eq!(vec![src.join("c (1)")],
+            items.iter().map(|s| s.destination.clone()).collect::<Vec<PathBuf>>());
+        assert!(plan_paste(&paths[2..], &src, FileOp::Cut, ConflictPolicy::Overwrite,
+                DEFAULT_RENAME_PATTERN, |_, _| None).unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
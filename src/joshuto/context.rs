@@ -2,12 +2,14 @@ extern crate ncurses;
 extern crate whoami;
 
 use std::path;
-use std::sync;
-use std::thread;
+use std::rc::Rc;
 
-use joshuto::command;
 use joshuto::config;
 use joshuto::history;
+use joshuto::input_history::InputHistory;
+use joshuto::job::JoshutoJobs;
+use joshuto::journal::JoshutoJournal;
+use joshuto::register::JoshutoRegisters;
 use joshuto::sort;
 use joshuto::structs::JoshutoDirList;
 use joshuto::ui;
@@ -19,16 +21,35 @@ use joshuto::theme_t;
 pub struct JoshutoContext {
     pub username: String,
     pub hostname: String,
-    pub threads: Vec<(sync::mpsc::Receiver<command::ProgressInfo>, thread::JoinHandle<i32>)>,
+    pub jobs: JoshutoJobs,
     pub views: JoshutoView,
     pub curr_tab_index: usize,
     pub tabs: Vec<JoshutoTab>,
+    pub input_history: InputHistory,
 
     pub config_t: config::JoshutoConfig,
+    /* shared so the running command's keymap can outlive a reload */
+    pub keymap_t: Rc<config::JoshutoKeymap>,
+
+    /* --choosedir, --choosefiles and --null */
+    pub choosedir: Option<path::PathBuf>,
+    pub choosefiles: Option<path::PathBuf>,
+    pub choose_null: bool,
+
+    /* named registers for copy_files, cut_files and paste_files */
+    pub registers: JoshutoRegisters,
+    /* register given with "x before a keybinding */
+    pub pending_register: Option<char>,
+
+    /* errors and notices kept for the messages command */
+    pub messages: Vec<String>,
+
+    /* file operations that undo and redo step through */
+    pub journal: JoshutoJournal,
 }
 
 impl<'a> JoshutoContext {
-    pub fn new(config_t: config::JoshutoConfig) -> Self
+    pub fn new(config_t: config::JoshutoConfig, keymap_t: config::JoshutoKeymap) -> Self
     {
         let username: String = whoami::username();
         let hostname: String = whoami::hostname();
@@ -39,11 +60,20 @@ impl<'a> JoshutoContext {
         JoshutoContext {
             username,
             hostname,
-            threads: Vec::new(),
+            jobs: JoshutoJobs::new(),
             views,
             curr_tab_index: 0,
             tabs: Vec::new(),
+            input_history: InputHistory::new(),
             config_t,
+            keymap_t: Rc::new(keymap_t),
+            choosedir: None,
+            choosefiles: None,
+            choose_null: false,
+            registers: JoshutoRegisters::new(),
+            pending_register: None,
+            messages: Vec::new(),
+            journal: JoshutoJournal::new(),
         }
     }
     pub fn curr_tab_ref(&'a self) -> &'a JoshutoTab
@@ -174,10 +204,11 @@ impl JoshutoTab {
 
         ncurses::waddstr(win.win, " ");
 
-        ncurses::wattron(win.win, ncurses::COLOR_PAIR(theme_t.directory.colorpair));
+        let theme = theme_t.read().unwrap();
+        ncurses::wattron(win.win, ncurses::COLOR_PAIR(theme.directory.colorpair));
         ncurses::waddstr(win.win, path_str);
         ncurses::waddstr(win.win, "/");
-        ncurses::wattroff(win.win, ncurses::COLOR_PAIR(theme_t.directory.colorpair));
+        ncurses::wattroff(win.win, ncurses::COLOR_PAIR(theme.directory.colorpair));
         if let Some(ref dirlist) = self.curr_list {
             if let Some(entry) = dirlist.get_curr_ref() {
                 ncurses::waddstr(win.win, &entry.file_name_as_string);
@@ -0,0 +1,76 @@
+extern crate xdg;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path;
+
+const HISTORY_DIR: &str = "history";
+const HISTORY_SIZE: usize = 100;
+
+/* input history of each prompt, keyed by the command owning the prompt */
+pub struct InputHistory {
+    map: HashMap<String, Vec<String>>,
+}
+
+impl InputHistory {
+    pub fn new() -> Self
+    {
+        InputHistory {
+            map: HashMap::new(),
+        }
+    }
+
+    fn history_file(kind: &str) -> Option<path::PathBuf>
+    {
+        let dirs = xdg::BaseDirectories::with_profile(::PROGRAM_NAME, "").ok()?;
+        let mut file = path::PathBuf::from(HISTORY_DIR);
+        file.push(kind);
+        dirs.place_data_file(file).ok()
+    }
+
+    fn read_history(kind: &str) -> Vec<String>
+    {
+        let contents = match Self::history_file(kind) {
+            Some(s) => fs::read_to_string(s).unwrap_or_default(),
+            None => String::new(),
+        };
+        contents.lines()
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    /* saving is best effort, a read-only data directory
+     * should not get in the way of using the prompt */
+    fn write_history(kind: &str, entries: &[String])
+    {
+        if let Some(file) = Self::history_file(kind) {
+            let mut contents = entries.join("\n");
+            contents.push('\n');
+            let _ = fs::write(file, contents);
+        }
+    }
+
+    pub fn get(&mut self, kind: &str) -> &[String]
+    {
+        self.map.entry(kind.to_string())
+            .or_insert_with(|| Self::read_history(kind))
+    }
+
+    pub fn push(&mut self, kind: &str, line: &str)
+    {
+        if line.is_empty() {
+            return;
+        }
+        let entries = self.map.entry(kind.to_string())
+            .or_insert_with(|| Self::read_history(kind));
+
+        entries.retain(|s| s != line);
+        entries.push(line.to_string());
+        if entries.len() > HISTORY_SIZE {
+            let overflow = entries.len() - HISTORY_SIZE;
+            entries.drain(..overflow);
+        }
+        Self::write_history(kind, entries);
+    }
+}
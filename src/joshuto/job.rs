@@ -0,0 +1,421 @@
+use std::io;
+use std::path;
+use std::slice;
+use std::sync;
+use std::sync::atomic;
+use std::sync::mpsc;
+use std::thread;
+use std::time;
+
+use joshuto::journal::FileAction;
+use joshuto::journal::JournalEntry;
+
+#[cfg(test)]
+mod test;
+
+/* how often running jobs are polled and their progress redrawn */
+pub const JOB_POLL_MILLIS: i32 = 200;
+/* finished jobs kept around for the jobs panel */
+const FINISHED_JOBS_KEPT: usize = 10;
+
+#[derive(Clone, Debug)]
+pub struct ProgressInfo {
+    pub bytes_finished: u64,
+    pub total_bytes: u64,
+}
+
+/* what a job's progress counts */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProgressUnit {
+    Bytes,
+    Files,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobStatus {
+    Running,
+    Paused,
+    Cancelled,
+    Finished,
+    Failed(String),
+}
+
+impl JobStatus {
+    pub fn is_done(&self) -> bool
+    {
+        *self != JobStatus::Running && *self != JobStatus::Paused
+    }
+
+    pub fn as_str(&self) -> &'static str
+    {
+        match self {
+            JobStatus::Running => "running",
+            JobStatus::Paused => "paused",
+            JobStatus::Cancelled => "cancelled",
+            JobStatus::Finished => "finished",
+            JobStatus::Failed(_) => "failed",
+        }
+    }
+}
+
+/* a file a job couldn't do its part on */
+#[derive(Debug)]
+pub struct FileError {
+    pub path: path::PathBuf,
+    pub error: io::Error,
+}
+
+/* how many files a job went through and which of them failed */
+#[derive(Debug, Default)]
+pub struct JobReport {
+    pub files: usize,
+    pub errors: Vec<FileError>,
+}
+
+impl JobReport {
+    pub fn new() -> Self
+    {
+        JobReport::default()
+    }
+
+    pub fn add(&mut self, path: &path::Path, result: io::Result<()>)
+    {
+        self.files += 1;
+        if let Err(error) = result {
+            self.errors.push(FileError {
+                path: path.to_path_buf(),
+                error,
+            });
+        }
+    }
+
+    /* "3 of 120 failed", None if nothing did */
+    pub fn summary(&self) -> Option<String>
+    {
+        if self.errors.is_empty() {
+            None
+        } else {
+            Some(format!("{} of {} failed", self.errors.len(), self.files))
+        }
+    }
+}
+
+/* Err is for a job that couldn't run at all, failures
+ * on single files go in the report */
+pub type JobResult = Result<JobReport, String>;
+
+/* flags set by the ui and checked by the job's thread */
+#[derive(Debug)]
+struct JobControl {
+    paused: atomic::AtomicBool,
+    cancelled: atomic::AtomicBool,
+}
+
+/* given to the job's thread to report progress and check for pause or cancel */
+pub struct JobHandle {
+    tx: mpsc::Sender<ProgressInfo>,
+    action_tx: mpsc::Sender<FileAction>,
+    control: sync::Arc<JobControl>,
+}
+
+impl JobHandle {
+    /* blocks while the job is paused, false once it is cancelled */
+    pub fn check(&self) -> bool
+    {
+        let wait_duration = time::Duration::from_millis(JOB_POLL_MILLIS as u64);
+        while self.control.paused.load(atomic::Ordering::SeqCst)
+                && !self.control.cancelled.load(atomic::Ordering::SeqCst) {
+            thread::sleep(wait_duration);
+        }
+        !self.control.cancelled.load(atomic::Ordering::SeqCst)
+    }
+
+    /* same as check, after reporting progress */
+    pub fn progress(&self, progress_info: ProgressInfo) -> bool
+    {
+        let _ = self.tx.send(progress_info);
+        self.check()
+    }
+
+    /* note a change the job made, for undo */
+    pub fn record(&self, action: FileAction)
+    {
+        let _ = self.action_tx.send(action);
+    }
+}
+
+#[derive(Debug)]
+pub struct JoshutoJob {
+    pub id: usize,
+    pub description: String,
+    pub status: JobStatus,
+    pub unit: ProgressUnit,
+    pub progress: ProgressInfo,
+    /* filled in once the job is done */
+    pub report: JobReport,
+    /* time spent running, not counting pauses */
+    elapsed: time::Duration,
+    resumed_at: Option<time::Instant>,
+    /* whether the ui has been told the job is done */
+    announced: bool,
+    control: sync::Arc<JobControl>,
+    rx: mpsc::Receiver<ProgressInfo>,
+    /* changes made so far, handed to the journal once the job is done */
+    actions: Vec<FileAction>,
+    action_rx: mpsc::Receiver<FileAction>,
+    handle: Option<thread::JoinHandle<JobResult>>,
+}
+
+impl JoshutoJob {
+    fn running_time(&self) -> time::Duration
+    {
+        match self.resumed_at {
+            Some(s) => self.elapsed + s.elapsed(),
+            None => self.elapsed,
+        }
+    }
+
+    fn stop_timer(&mut self)
+    {
+        if let Some(s) = self.resumed_at.take() {
+            self.elapsed += s.elapsed();
+        }
+    }
+
+    pub fn percentage(&self) -> f32
+    {
+        if self.progress.total_bytes == 0 {
+            return 0.0;
+        }
+        (self.progress.bytes_finished as f64 / self.progress.total_bytes as f64) as f32
+    }
+
+    /* units of progress per second */
+    pub fn throughput(&self) -> f64
+    {
+        let secs = duration_secs(self.running_time());
+        if secs <= 0.0 {
+            return 0.0;
+        }
+        self.progress.bytes_finished as f64 / secs
+    }
+
+    pub fn eta(&self) -> Option<time::Duration>
+    {
+        let throughput = self.throughput();
+        if throughput <= 0.0 || self.status.is_done() {
+            return None;
+        }
+        let remaining = self.progress.total_bytes.saturating_sub(self.progress.bytes_finished);
+        Some(time::Duration::from_millis((remaining as f64 / throughput * 1000.0) as u64))
+    }
+
+    fn finish(&mut self, result: Option<thread::Result<JobResult>>)
+    {
+        self.stop_timer();
+        self.status = match result {
+                Some(Ok(Ok(report))) => {
+                    self.report = report;
+                    JobStatus::Finished
+                },
+                Some(Ok(Err(e))) => JobStatus::Failed(e),
+                Some(Err(_)) => JobStatus::Failed(String::from("job panicked")),
+                None => JobStatus::Finished,
+            };
+        if self.control.cancelled.load(atomic::Ordering::SeqCst) {
+            self.status = JobStatus::Cancelled;
+        }
+    }
+}
+
+fn duration_secs(duration: time::Duration) -> f64
+{
+    duration.as_secs() as f64 + f64::from(duration.subsec_millis()) / 1000.0
+}
+
+/* background file operations, each with an id the jobs panel refers to */
+#[derive(Debug)]
+pub struct JoshutoJobs {
+    next_id: usize,
+    jobs: Vec<JoshutoJob>,
+}
+
+impl JoshutoJobs {
+    pub fn new() -> Self
+    {
+        JoshutoJobs {
+            next_id: 1,
+            jobs: Vec::new(),
+        }
+    }
+
+    /* run f in its own thread as a new job, returning the job's id */
+    pub fn spawn<F>(&mut self, description: String, unit: ProgressUnit, total: u64, f: F) -> usize
+        where F: FnOnce(JobHandle) -> JobResult + Send + 'static
+    {
+        let (tx, rx) = mpsc::channel();
+        let (action_tx, action_rx) = mpsc::channel();
+        let control = sync::Arc::new(JobControl {
+                paused: atomic::AtomicBool::new(false),
+                cancelled: atomic::AtomicBool::new(false),
+            });
+        let job_handle = JobHandle {
+                tx,
+                action_tx,
+                control: control.clone(),
+            };
+        let handle = thread::spawn(move || f(job_handle));
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.jobs.push(JoshutoJob {
+                id,
+                description,
+                status: JobStatus::Running,
+                unit,
+                progress: ProgressInfo {
+                    bytes_finished: 0,
+                    total_bytes: total,
+                },
+                report: JobReport::new(),
+                elapsed: time::Duration::from_secs(0),
+                resumed_at: Some(time::Instant::now()),
+                announced: false,
+                control,
+                rx,
+                actions: Vec::new(),
+                action_rx,
+                handle: Some(handle),
+            });
+        id
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, JoshutoJob>
+    {
+        self.jobs.iter()
+    }
+
+    pub fn is_running(&self) -> bool
+    {
+        self.jobs.iter().any(|job| !job.status.is_done())
+    }
+
+    /* overall progress of the jobs still running */
+    pub fn percentage(&self) -> Option<f32>
+    {
+        let running: Vec<&JoshutoJob> = self.jobs.iter()
+                .filter(|job| !job.status.is_done())
+                .collect();
+        if running.is_empty() {
+            return None;
+        }
+        let total: f32 = running.iter().map(|job| job.percentage()).sum();
+        Some(total / running.len() as f32)
+    }
+
+    fn get_mut(&mut self, id: usize) -> Result<&mut JoshutoJob, String>
+    {
+        match self.jobs.iter_mut().find(|job| job.id == id) {
+            Some(s) => Ok(s),
+            None => Err(format!("No such job: {}", id)),
+        }
+    }
+
+    pub fn pause(&mut self, id: usize) -> Result<(), String>
+    {
+        let job = self.get_mut(id)?;
+        if job.status != JobStatus::Running {
+            return Err(format!("Job {} is {}", id, job.status.as_str()));
+        }
+        job.control.paused.store(true, atomic::Ordering::SeqCst);
+        job.stop_timer();
+        job.status = JobStatus::Paused;
+        Ok(())
+    }
+
+    pub fn resume(&mut self, id: usize) -> Result<(), String>
+    {
+        let job = self.get_mut(id)?;
+        if job.status != JobStatus::Paused {
+            return Err(format!("Job {} is {}", id, job.status.as_str()));
+        }
+        job.control.paused.store(false, atomic::Ordering::SeqCst);
+        job.resumed_at = Some(time::Instant::now());
+        job.status = JobStatus::Running;
+        Ok(())
+    }
+
+    /* the job stops at its next progress report */
+    pub fn cancel(&mut self, id: usize) -> Result<(), String>
+    {
+        let job = self.get_mut(id)?;
+        if job.status.is_done() {
+            return Err(format!("Job {} is {}", id, job.status.as_str()));
+        }
+        job.control.cancelled.store(true, atomic::Ordering::SeqCst);
+        Ok(())
+    }
+
+    /* cancel the jobs still running and wait for them to stop,
+     * so none is cut off part way through a file */
+    pub fn cancel_all(&mut self)
+    {
+        for job in self.jobs.iter_mut().filter(|job| !job.status.is_done()) {
+            job.control.cancelled.store(true, atomic::Ordering::SeqCst);
+            let result = job.handle.take().map(|s| s.join());
+            job.finish(result);
+        }
+    }
+
+    /* take in progress reports without blocking, and reap jobs that are done */
+    pub fn poll(&mut self)
+    {
+        for job in self.jobs.iter_mut().filter(|job| !job.status.is_done()) {
+            loop {
+                match job.rx.try_recv() {
+                    Ok(s) => job.progress = s,
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        let result = job.handle.take().map(|s| s.join());
+                        job.finish(result);
+                        break;
+                    },
+                }
+            }
+            job.actions.extend(job.action_rx.try_iter());
+        }
+
+        let mut finished = self.jobs.iter().filter(|job| job.status.is_done()).count();
+        self.jobs.retain(|job| {
+                if finished > FINISHED_JOBS_KEPT && job.status.is_done() && job.announced {
+                    finished -= 1;
+                    false
+                } else {
+                    true
+                }
+            });
+    }
+
+    /* jobs that finished since the last call, to be reported once */
+    pub fn take_finished(&mut self) -> Vec<&JoshutoJob>
+    {
+        let mut finished: Vec<&JoshutoJob> = Vec::new();
+        for job in self.jobs.iter_mut() {
+            if job.status.is_done() && !job.announced {
+                job.announced = true;
+                finished.push(job);
+            }
+        }
+        finished
+    }
+
+    /* what each job that is done changed, for the journal */
+    pub fn take_journal(&mut self) -> Vec<JournalEntry>
+    {
+        self.jobs.iter_mut()
+            .filter(|job| job.status.is_done() && !job.actions.is_empty())
+            .map(|job| JournalEntry::new(job.description.clone(),
+                    job.actions.drain(..).collect()))
+            .collect()
+    }
+}
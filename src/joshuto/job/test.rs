@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+
+    fn wait_for(jobs: &mut JoshutoJobs)
+    {
+        for _ in 0..100 {
+            jobs.poll();
+            if !jobs.is_running() {
+                return;
+            }
+            thread::sleep(time::Duration::from_millis(10));
+        }
+        panic!("job did not finish");
+    }
+
+    #[test]
+    fn finished_and_failed_jobs() {
+        let mut jobs = JoshutoJobs::new();
+        let first = jobs.spawn(String::from("ok"), ProgressUnit::Files, 2, |handle| {
+            handle.progress(ProgressInfo { bytes_finished: 2, total_bytes: 2 });
+            let mut report = JobReport::new();
+            report.add(path::Path::new("a"), Ok(()));
+            report.add(path::Path::new("b"), Err(io::Error::from(io::ErrorKind::NotFound)));
+            Ok(report)
+        });
+        let second = jobs.spawn(String::from("err"), ProgressUnit::Files, 1, |_| {
+            Err(String::from("broken"))
+        });
+        assert_eq!(1, first);
+        assert_eq!(2, second);
+        wait_for(&mut jobs);
+
+        let statuses: Vec<JobStatus> = jobs.iter().map(|job| job.status.clone()).collect();
+        assert_eq!(vec![JobStatus::Finished, JobStatus::Failed(String::from("broken"))],
+            statuses);
+        let first_job = jobs.iter().next().unwrap();
+        assert_eq!(Some(String::from("1 of 2 failed")), first_job.report.summary());
+        assert_eq!(path::Path::new("b"), first_job.report.errors[0].path.as_path());
+        assert_eq!(2, jobs.take_finished().len());
+        assert!(jobs.take_finished().is_empty());
+        assert!(jobs.cancel(first).is_err());
+    }
+
+    #[test]
+    fn pause_and_cancel() {
+        let mut jobs = JoshutoJobs::new();
+        let id = jobs.spawn(String::from("loop"), ProgressUnit::Bytes, 100, |handle| {
+            let mut progress_info = ProgressInfo { bytes_finished: 0, total_bytes: 100 };
+            while handle.progress(progress_info.clone()) {
+                progress_info.bytes_finished = (progress_info.bytes_finished + 1) % 100;
+                thread::sleep(time::Duration::from_millis(1));
+            }
+            Ok(JobReport::new())
+        });
+        assert!(jobs.pause(id).is_ok());
+        assert!(jobs.pause(id).is_err());
+        assert!(jobs.resume(id).is_ok());
+        assert!(jobs.pause(id).is_ok());
+        assert!(jobs.cancel(id).is_ok());
+        wait_for(&mut jobs);
+        assert_eq!(Some(JobStatus::Cancelled), jobs.iter().next().map(|job| job.status.clone()));
+        assert!(jobs.pause(42).is_err());
+    }
+}
@@ -0,0 +1,345 @@
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path;
+
+use joshuto::conflict;
+use joshuto::trash;
+use joshuto::trash::TrashEntry;
+use joshuto::transfer;
+
+#[cfg(test)]
+mod test;
+
+/* undo steps kept for the session */
+const JOURNAL_SIZE: usize = 100;
+
+/* identifies a file and its last modification, to tell whether
+ * it changed after an action was done or undone */
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileStamp {
+    dev: u64,
+    ino: u64,
+    size: u64,
+    mtime: i64,
+    mtime_nsec: i64,
+}
+
+impl FileStamp {
+    pub fn new(path: &path::Path) -> Option<Self>
+    {
+        let metadata = fs::symlink_metadata(path).ok()?;
+        Some(FileStamp {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+            size: metadata.size(),
+            mtime: metadata.mtime(),
+            mtime_nsec: metadata.mtime_nsec(),
+        })
+    }
+}
+
+/* a change to the filesystem that can be undone and redone */
+#[derive(Clone, Debug)]
+pub enum FileAction {
+    /* a rename or a file moved by pasting a cut */
+    Move {
+        source: path::PathBuf,
+        destination: path::PathBuf,
+        /* of whichever of source and destination the file is at */
+        stamp: Option<FileStamp>,
+    },
+    /* the directories made, outermost first */
+    Mkdir(Vec<path::PathBuf>),
+    /* original_path's stamp is kept once the file is restored */
+    Trash(TrashEntry, Option<FileStamp>),
+    SetMode {
+        path: path::PathBuf,
+        old_mode: u32,
+        new_mode: u32,
+    },
+}
+
+fn check_unchanged(path: &path::Path, stamp: &Option<FileStamp>) -> Result<(), String>
+{
+    match FileStamp::new(path) {
+        None => Err(format!("{} no longer exists", path.to_string_lossy())),
+        Some(ref s) if stamp.as_ref() != Some(s) => {
+            Err(format!("{} has changed since", path.to_string_lossy()))
+        },
+        Some(_) => Ok(()),
+    }
+}
+
+fn check_free(path: &path::Path) -> Result<(), String>
+{
+    if conflict::path_exists(path) {
+        Err(format!("{} already exists", path.to_string_lossy()))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_mode(path: &path::Path, mode: u32) -> Result<(), String>
+{
+    match fs::symlink_metadata(path) {
+        Ok(ref s) if s.mode() & 0o7777 == mode => Ok(()),
+        Ok(_) => Err(format!("{} has changed mode since", path.to_string_lossy())),
+        Err(e) => Err(format!("{}: {}", path.to_string_lossy(), e)),
+    }
+}
+
+/* moves across filesystems copy everything, so they are left to cut and
+ * paste where they run as a job, and cut doesn't journal them */
+fn check_same_fs(path: &path::Path, destination: &path::Path) -> Result<(), String>
+{
+    if transfer::same_filesystem(path, destination) {
+        Ok(())
+    } else {
+        Err(format!("{} is on another filesystem, move it with cut and paste",
+                path.to_string_lossy()))
+    }
+}
+
+fn set_mode(path: &path::Path, mode: u32) -> io::Result<()>
+{
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+impl FileAction {
+    pub fn new_move(source: path::PathBuf, destination: path::PathBuf) -> Self
+    {
+        let stamp = FileStamp::new(&destination);
+        FileAction::Move {
+            source,
+            destination,
+            stamp,
+        }
+    }
+
+    /* whether undo can run without losing anything */
+    fn check_undo(&self) -> Result<(), String>
+    {
+        match self {
+            FileAction::Move { source, destination, stamp } => {
+                check_unchanged(destination, stamp)?;
+                check_same_fs(destination, source)?;
+                check_free(source)
+            },
+            /* each directory may only hold the next one made */
+            FileAction::Mkdir(paths) => {
+                for (i, path) in paths.iter().enumerate() {
+                    let mut contents = match fs::read_dir(path) {
+                            Ok(s) => s,
+                            Err(e) => return Err(format!("{}: {}", path.to_string_lossy(), e)),
+                        };
+                    let next = paths.get(i + 1);
+                    if contents.any(|s| s.map(|s| Some(&s.path()) != next).unwrap_or(true)) {
+                        return Err(format!("{} is not empty", path.to_string_lossy()));
+                    }
+                }
+                Ok(())
+            },
+            FileAction::Trash(entry, _) => {
+                if !conflict::path_exists(&entry.trash_path) {
+                    return Err(format!("{} is no longer in the trash",
+                            entry.original_path.to_string_lossy()));
+                }
+                check_free(&entry.original_path)
+            },
+            FileAction::SetMode { path, new_mode, .. } => check_mode(path, *new_mode),
+        }
+    }
+
+    fn check_redo(&self) -> Result<(), String>
+    {
+        match self {
+            FileAction::Move { source, destination, stamp } => {
+                check_unchanged(source, stamp)?;
+                check_same_fs(source, destination)?;
+                check_free(destination)
+            },
+            FileAction::Mkdir(paths) => {
+                match paths.first() {
+                    Some(s) => check_free(s),
+                    None => Ok(()),
+                }
+            },
+            FileAction::Trash(entry, stamp) => check_unchanged(&entry.original_path, stamp),
+            FileAction::SetMode { path, old_mode, .. } => check_mode(path, *old_mode),
+        }
+    }
+
+    fn undo(&mut self) -> io::Result<()>
+    {
+        match self {
+            FileAction::Move { source, destination, stamp } => {
+                fs::rename(&*destination, &*source)?;
+                *stamp = FileStamp::new(source);
+            },
+            FileAction::Mkdir(paths) => {
+                for path in paths.iter().rev() {
+                    fs::remove_dir(path)?;
+                }
+            },
+            FileAction::Trash(entry, stamp) => {
+                entry.restore()?;
+                *stamp = FileStamp::new(&entry.original_path);
+            },
+            FileAction::SetMode { path, old_mode, .. } => set_mode(path, *old_mode)?,
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self) -> io::Result<()>
+    {
+        match self {
+            FileAction::Move { source, destination, stamp } => {
+                fs::rename(&*source, &*destination)?;
+                *stamp = FileStamp::new(destination);
+            },
+            FileAction::Mkdir(paths) => {
+                for path in paths.iter() {
+                    fs::create_dir(path)?;
+                }
+            },
+            FileAction::Trash(entry, _) => {
+                let path = entry.original_path.clone();
+                *entry = trash::trash_dir_for(&path)?.trash(&path)?;
+            },
+            FileAction::SetMode { path, new_mode, .. } => set_mode(path, *new_mode)?,
+        }
+        Ok(())
+    }
+}
+
+/* the actions done by one command, undone and redone together */
+#[derive(Clone, Debug)]
+pub struct JournalEntry {
+    pub description: String,
+    pub actions: Vec<FileAction>,
+}
+
+impl JournalEntry {
+    pub fn new(description: String, actions: Vec<FileAction>) -> Self
+    {
+        JournalEntry {
+            description,
+            actions,
+        }
+    }
+
+    /* every action is checked before any is undone, in reverse order.
+     * Each one undone moves to undone, so a failure leaves the rest in self */
+    fn undo(&mut self, undone: &mut Vec<FileAction>) -> Result<(), String>
+    {
+        for action in &self.actions {
+            action.check_undo()?;
+        }
+        while let Some(mut action) = self.actions.pop() {
+            if let Err(e) = action.undo() {
+                self.actions.push(action);
+                return Err(e.to_string());
+            }
+            undone.insert(0, action);
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self, redone: &mut Vec<FileAction>) -> Result<(), String>
+    {
+        for action in &self.actions {
+            action.check_redo()?;
+        }
+        while !self.actions.is_empty() {
+            let mut action = self.actions.remove(0);
+            if let Err(e) = action.redo() {
+                self.actions.insert(0, action);
+                return Err(e.to_string());
+            }
+            redone.push(action);
+        }
+        Ok(())
+    }
+}
+
+/* file operations done this session, for undo and redo.
+ * An entry that can't be undone or redone is dropped, unless it failed
+ * part way: then the part done and the part left are kept apart */
+#[derive(Debug)]
+pub struct JoshutoJournal {
+    undo_list: Vec<JournalEntry>,
+    redo_list: Vec<JournalEntry>,
+}
+
+impl JoshutoJournal {
+    pub fn new() -> Self
+    {
+        JoshutoJournal {
+            undo_list: Vec::new(),
+            redo_list: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, entry: JournalEntry)
+    {
+        if entry.actions.is_empty() {
+            return;
+        }
+        if self.undo_list.len() >= JOURNAL_SIZE {
+            self.undo_list.remove(0);
+        }
+        self.undo_list.push(entry);
+        self.redo_list.clear();
+    }
+
+    /* the description of what was undone */
+    pub fn undo(&mut self) -> Result<String, String>
+    {
+        let mut entry = match self.undo_list.pop() {
+                Some(s) => s,
+                None => return Err(String::from("Nothing to undo")),
+            };
+        let mut undone = Vec::new();
+        let result = entry.undo(&mut undone);
+        let undone_len = undone.len();
+        if !undone.is_empty() {
+            self.redo_list.push(JournalEntry::new(entry.description.clone(), undone));
+        }
+        match result {
+            Ok(_) => Ok(entry.description),
+            Err(e) if undone_len == 0 => Err(format!("Can't undo {}: {}", entry.description, e)),
+            Err(e) => {
+                let msg = format!("Can't undo all of {}: {}, {} of {} undone",
+                        entry.description, e, undone_len, undone_len + entry.actions.len());
+                self.undo_list.push(entry);
+                Err(msg)
+            },
+        }
+    }
+
+    pub fn redo(&mut self) -> Result<String, String>
+    {
+        let mut entry = match self.redo_list.pop() {
+                Some(s) => s,
+                None => return Err(String::from("Nothing to redo")),
+            };
+        let mut redone = Vec::new();
+        let result = entry.redo(&mut redone);
+        let redone_len = redone.len();
+        if !redone.is_empty() {
+            self.undo_list.push(JournalEntry::new(entry.description.clone(), redone));
+        }
+        match result {
+            Ok(_) => Ok(entry.description),
+            Err(e) if redone_len == 0 => Err(format!("Can't redo {}: {}", entry.description, e)),
+            Err(e) => {
+                let msg = format!("Can't redo all of {}: {}, {} of {} redone",
+                        entry.description, e, redone_len, redone_len + entry.actions.len());
+                self.redo_list.push(entry);
+                Err(msg)
+            },
+        }
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use joshuto::test_helpers::temp_dir;
+
+    #[test]
+    fn undo_and_redo_move() {
+        let dir = temp_dir("journal-move");
+        let (a, b) = (dir.join("a"), dir.join("b"));
+        fs::write(&a, "a").unwrap();
+        fs::rename(&a, &b).unwrap();
+
+        let mut journal = JoshutoJournal::new();
+        journal.push(JournalEntry::new(String::from("rename a"),
+                vec![FileAction::new_move(a.clone(), b.clone())]));
+        assert_eq!(Ok(String::from("rename a")), journal.undo());
+        assert!(a.exists() && !b.exists());
+        assert_eq!(Ok(String::from("rename a")), journal.redo());
+        assert!(!a.exists() && b.exists());
+        assert!(journal.redo().is_err());
+
+        /* b is modified after the rename, so undoing it would lose the change */
+        fs::write(&b, "changed").unwrap();
+        assert!(journal.undo().is_err());
+        assert!(b.exists());
+        assert!(journal.undo().is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn undo_part_way() {
+        let dir = temp_dir("journal-part");
+        let (sub, c, d) = (dir.join("sub"), dir.join("sub/c"), dir.join("d"));
+        let (a, b) = (dir.join("a"), dir.join("b"));
+        fs::write(&d, "c").unwrap();
+        fs::write(&b, "a").unwrap();
+
+        let mut journal = JoshutoJournal::new();
+        journal.push(JournalEntry::new(String::from("move 2 files"),
+                vec![FileAction::new_move(c.clone(), d.clone()),
+                    FileAction::new_move(a.clone(), b.clone())]));
+
+        /* b goes back to a, then c has nowhere to go */
+        assert!(journal.undo().is_err());
+        assert!(a.exists() && d.exists());
+
+        fs::create_dir(&sub).unwrap();
+        assert_eq!(Ok(String::from("move 2 files")), journal.undo());
+        assert!(c.exists() && !d.exists());
+
+        journal.redo().unwrap();
+        journal.redo().unwrap();
+        assert!(b.exists() && d.exists());
+        assert!(!a.exists() && !c.exists());
+        assert!(journal.redo().is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn undo_mkdir_and_set_mode() {
+        let dir = temp_dir("journal-mkdir");
+        let (outer, inner) = (dir.join("x"), dir.join("x/y"));
+        fs::create_dir_all(&inner).unwrap();
+        set_mode(&inner, 0o700).unwrap();
+
+        let mut journal = JoshutoJournal::new();
+        journal.push(JournalEntry::new(String::from("mkdir x/y"),
+                vec![FileAction::Mkdir(vec![outer.clone(), inner.clone()])]));
+        journal.push(JournalEntry::new(String::from("set_mode y"),
+                vec![FileAction::SetMode { path: inner.clone(), old_mode: 0o755, new_mode: 0o700 }]));
+
+        journal.undo().unwrap();
+        assert_eq!(0o755, fs::metadata(&inner).unwrap().mode() & 0o7777);
+
+        fs::write(inner.join("f"), "").unwrap();
+        assert!(journal.undo().is_err());
+        assert!(inner.exists());
+
+        journal.push(JournalEntry::new(String::from("mkdir x/y"),
+                vec![FileAction::Mkdir(vec![outer.clone(), inner.clone()])]));
+        fs::remove_file(inner.join("f")).unwrap();
+        journal.undo().unwrap();
+        assert!(!outer.exists());
+        journal.redo().unwrap();
+        assert!(inner.is_dir());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
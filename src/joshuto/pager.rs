@@ -0,0 +1,85 @@
+extern crate ncurses;
+
+use joshuto::config::keymap;
+use joshuto::textfield::JoshutoTextField;
+use joshuto::ui;
+use joshuto::window;
+
+/* lines containing filter, ignoring case */
+fn filter_lines<'a>(lines: &'a [String], filter: &str) -> Vec<&'a String>
+{
+    let filter = filter.to_lowercase();
+    lines.iter().filter(|s| s.to_lowercase().contains(filter.as_str())).collect()
+}
+
+/* full screen, scrollable view of lines of text, / filters the lines.
+ * blocks until the user closes it with q or Escape */
+pub fn show_pager(title: &str, lines: &[String])
+{
+    let (term_rows, term_cols) = ui::getmaxyx();
+    let win = window::JoshutoPanel::new(term_rows, term_cols, (0, 0));
+    ncurses::keypad(win.win, true);
+    ncurses::timeout(-1);
+    win.move_to_top();
+
+    let page_rows = (term_rows - 2).max(1) as usize;
+    let mut filter = String::new();
+    let mut shown: Vec<&String> = lines.iter().collect();
+    let mut max_start = shown.len().saturating_sub(page_rows);
+    let mut start: usize = 0;
+
+    loop {
+        ncurses::werase(win.win);
+        ncurses::wattron(win.win, ncurses::A_BOLD());
+        if filter.is_empty() {
+            ncurses::mvwaddnstr(win.win, 0, 0, title, term_cols);
+        } else {
+            ncurses::mvwaddnstr(win.win, 0, 0,
+                    format!("{} (/{})", title, filter).as_str(), term_cols);
+        }
+        ncurses::wattroff(win.win, ncurses::A_BOLD());
+
+        let end = (start + page_rows).min(shown.len());
+        for (i, line) in shown[start..end].iter().enumerate() {
+            ncurses::mvwaddnstr(win.win, i as i32 + 1, 0, line.as_str(), term_cols);
+        }
+
+        let status = format!("{}-{}/{}  (/ to filter, q to close)",
+                (start + 1).min(end), end, shown.len());
+        ncurses::wattron(win.win, ncurses::A_STANDOUT());
+        ncurses::mvwaddnstr(win.win, term_rows - 1, 0, status.as_str(), term_cols);
+        ncurses::wattroff(win.win, ncurses::A_STANDOUT());
+        win.queue_for_refresh();
+        ncurses::doupdate();
+
+        let ch = ncurses::wgetch(win.win);
+        if ch == keymap::ESCAPE || ch == 'q' as i32 {
+            break;
+        } else if ch == ncurses::KEY_UP || ch == 'k' as i32 {
+            start = start.saturating_sub(1);
+        } else if ch == ncurses::KEY_DOWN || ch == 'j' as i32 {
+            start = (start + 1).min(max_start);
+        } else if ch == ncurses::KEY_PPAGE {
+            start = start.saturating_sub(page_rows);
+        } else if ch == ncurses::KEY_NPAGE || ch == ' ' as i32 {
+            start = (start + page_rows).min(max_start);
+        } else if ch == ncurses::KEY_HOME || ch == 'g' as i32 {
+            start = 0;
+        } else if ch == ncurses::KEY_END || ch == 'G' as i32 {
+            start = max_start;
+        } else if ch == '/' as i32 {
+            let user_input = {
+                    let textfield = JoshutoTextField::new(1, term_cols,
+                            (term_rows as usize - 1, 0), String::from("/"));
+                    textfield.readline_with_initial(filter.as_str(), "")
+                };
+            ncurses::timeout(-1);
+            if let Some(s) = user_input {
+                filter = s;
+                shown = filter_lines(lines, filter.as_str());
+                max_start = shown.len().saturating_sub(page_rows);
+                start = 0;
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+use std::path;
+
+#[cfg(test)]
+mod test;
+
+/* register used when none is given */
+pub const DEFAULT_REGISTER: char = '"';
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FileOp {
+    Cut,
+    Copy,
+}
+
+impl FileOp {
+    pub fn as_str(self) -> &'static str
+    {
+        match self {
+            FileOp::Cut => "cut",
+            FileOp::Copy => "copy",
+        }
+    }
+}
+
+/* files yanked into a register and what pasting them does */
+#[derive(Clone, Debug)]
+pub struct Register {
+    pub operation: FileOp,
+    pub paths: Vec<path::PathBuf>,
+}
+
+/* vim style named registers, a-z, 0-9 and the default " */
+#[derive(Debug)]
+pub struct JoshutoRegisters {
+    registers: BTreeMap<char, Register>,
+}
+
+impl JoshutoRegisters {
+    pub fn new() -> Self
+    {
+        JoshutoRegisters {
+            registers: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_valid_name(name: char) -> bool
+    {
+        name.is_ascii_lowercase() || name.is_ascii_digit() || name == DEFAULT_REGISTER
+    }
+
+    pub fn get(&self, name: char) -> Option<&Register>
+    {
+        self.registers.get(&name)
+    }
+
+    pub fn set(&mut self, name: char, register: Register)
+    {
+        self.registers.insert(name, register);
+    }
+
+    /* a cut register is emptied once it is pasted, the files are gone from their source */
+    pub fn take(&mut self, name: char) -> Option<Register>
+    {
+        match self.registers.get(&name) {
+            Some(s) if s.operation == FileOp::Cut => self.registers.remove(&name),
+            Some(s) => Some(s.clone()),
+            None => None,
+        }
+    }
+
+    /* one line per register followed by its paths, for the registers panel */
+    pub fn lines(&self) -> Vec<String>
+    {
+        let mut lines: Vec<String> = Vec::new();
+        for (name, register) in &self.registers {
+            lines.push(format!("\"{}  {}  {} file(s)", name,
+                    register.operation.as_str(), register.paths.len()));
+            for path in &register.paths {
+                lines.push(format!("      {}", path.to_string_lossy()));
+            }
+        }
+        lines
+    }
+}
@@ -0,0 +1,30 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use std::path::PathBuf;
+    #[test]
+    fn register_names() {
+        assert!(JoshutoRegisters::is_valid_name('a'));
+        assert!(JoshutoRegisters::is_valid_name('7'));
+        assert!(JoshutoRegisters::is_valid_name(DEFAULT_REGISTER));
+        assert!(!JoshutoRegisters::is_valid_name('A'));
+        assert!(!JoshutoRegisters::is_valid_name(' '));
+    }
+    #[test]
+    fn take_empties_cut_registers() {
+        let mut registers = JoshutoRegisters::new();
+        registers.set('a', Register {
+            operation: FileOp::Copy,
+            paths: vec![PathBuf::from("/tmp/a")],
+        });
+        registers.set('b', Register {
+            operation: FileOp::Cut,
+            paths: vec![PathBuf::from("/tmp/b")],
+        });
+        assert!(registers.take('a').is_some());
+        assert!(registers.take('a').is_some());
+        assert!(registers.take('b').is_some());
+        assert!(registers.take('b').is_none());
+        assert!(registers.take('c').is_none());
+    }
+}
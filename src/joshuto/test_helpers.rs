@@ -0,0 +1,13 @@
+use std::env;
+use std::fs;
+use std::path;
+use std::process;
+
+/* an empty directory for a test to work in, named after it */
+pub fn temp_dir(name: &str) -> path::PathBuf
+{
+    let dir = env::temp_dir().join(format!("joshuto-test-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -1,12 +1,307 @@
 extern crate ncurses;
 extern crate unicode_width;
 
+use joshuto::completion;
+use joshuto::completion::JoshutoCompleter;
 use joshuto::config::keymap;
+use joshuto::ui;
 use joshuto::window;
 
+#[cfg(test)]
+mod test;
+
+const fn ctrl(ch: char) -> i32
+{
+    ch as i32 & 0x1F
+}
+
+/* how long to wait after ESC for the rest of an Alt sequence */
+const ESCAPE_TIMEOUT: i32 = 25;
+
+fn char_width(ch: char) -> usize
+{
+    unicode_width::UnicodeWidthChar::width(ch).unwrap_or(1)
+}
+
+fn is_word_char(ch: char) -> bool
+{
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/* editing state of a single line, independent of ncurses */
+#[derive(Clone, Debug)]
+pub struct LineBuffer {
+    buffer: Vec<char>,
+    index: usize,
+    kill_ring: Vec<String>,
+    /* consecutive kills are merged into one kill ring entry */
+    last_was_kill: bool,
+    /* (start, end) of the text inserted by the last yank */
+    last_yank: Option<(usize, usize)>,
+    yank_index: usize,
+}
+
+impl LineBuffer {
+    pub fn new(prefix: &str, suffix: &str) -> Self
+    {
+        let mut buffer: Vec<char> = prefix.chars().collect();
+        let index = buffer.len();
+        buffer.extend(suffix.chars());
+
+        LineBuffer {
+            buffer,
+            index,
+            kill_ring: Vec::new(),
+            last_was_kill: false,
+            last_yank: None,
+            yank_index: 0,
+        }
+    }
+
+    pub fn as_slice(&self) -> &[char]
+    {
+        &self.buffer
+    }
+
+    pub fn index(&self) -> usize
+    {
+        self.index
+    }
+
+    /* display width of everything before the cursor */
+    pub fn cursor_width(&self) -> usize
+    {
+        self.buffer[..self.index].iter().map(|ch| char_width(*ch)).sum()
+    }
+
+    pub fn set_contents(&mut self, contents: &[char])
+    {
+        self.buffer = contents.to_vec();
+        self.index = self.buffer.len();
+        self.reset_state();
+    }
+
+    /* replaces everything from start up to the cursor with word */
+    pub fn replace_before_cursor(&mut self, start: usize, word: &str)
+    {
+        let word: Vec<char> = word.chars().collect();
+        let word_len = word.len();
+        self.buffer.splice(start..self.index, word);
+        self.index = start + word_len;
+        self.reset_state();
+    }
+
+    fn reset_state(&mut self)
+    {
+        self.last_was_kill = false;
+        self.last_yank = None;
+    }
+
+    pub fn insert(&mut self, ch: char)
+    {
+        self.buffer.insert(self.index, ch);
+        self.index += 1;
+        self.reset_state();
+    }
+
+    pub fn backspace(&mut self)
+    {
+        if self.index > 0 {
+            self.index -= 1;
+            self.buffer.remove(self.index);
+        }
+        self.reset_state();
+    }
+
+    pub fn delete(&mut self)
+    {
+        if self.index < self.buffer.len() {
+            self.buffer.remove(self.index);
+        }
+        self.reset_state();
+    }
+
+    pub fn move_left(&mut self)
+    {
+        if self.index > 0 {
+            self.index -= 1;
+        }
+        self.reset_state();
+    }
+
+    pub fn move_right(&mut self)
+    {
+        if self.index < self.buffer.len() {
+            self.index += 1;
+        }
+        self.reset_state();
+    }
+
+    pub fn move_home(&mut self)
+    {
+        self.index = 0;
+        self.reset_state();
+    }
+
+    pub fn move_end(&mut self)
+    {
+        self.index = self.buffer.len();
+        self.reset_state();
+    }
+
+    fn prev_word_start(&self) -> usize
+    {
+        let mut i = self.index;
+        while i > 0 && !is_word_char(self.buffer[i - 1]) {
+            i -= 1;
+        }
+        while i > 0 && is_word_char(self.buffer[i - 1]) {
+            i -= 1;
+        }
+        i
+    }
+
+    fn next_word_end(&self) -> usize
+    {
+        let len = self.buffer.len();
+        let mut i = self.index;
+        while i < len && !is_word_char(self.buffer[i]) {
+            i += 1;
+        }
+        while i < len && is_word_char(self.buffer[i]) {
+            i += 1;
+        }
+        i
+    }
+
+    pub fn move_word_left(&mut self)
+    {
+        self.index = self.prev_word_start();
+        self.reset_state();
+    }
+
+    pub fn move_word_right(&mut self)
+    {
+        self.index = self.next_word_end();
+        self.reset_state();
+    }
+
+    /* Ctrl-W, kills the whitespace separated word before the cursor */
+    pub fn kill_word_backward(&mut self)
+    {
+        let end = self.index;
+        let mut start = end;
+        while start > 0 && self.buffer[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !self.buffer[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        self.kill_backward(start, end);
+    }
+
+    /* Ctrl-U */
+    pub fn kill_to_start(&mut self)
+    {
+        let end = self.index;
+        self.kill_backward(0, end);
+    }
+
+    /* Ctrl-K */
+    pub fn kill_to_end(&mut self)
+    {
+        let start = self.index;
+        let end = self.buffer.len();
+        self.kill_forward(start, end);
+    }
+
+    fn kill_backward(&mut self, start: usize, end: usize)
+    {
+        if start == end {
+            return;
+        }
+        let killed: String = self.buffer.drain(start..end).collect();
+        self.index = start;
+        match self.kill_ring.last_mut() {
+            Some(last) if self.last_was_kill => last.insert_str(0, &killed),
+            _ => self.kill_ring.push(killed),
+        }
+        self.last_was_kill = true;
+        self.last_yank = None;
+    }
+
+    fn kill_forward(&mut self, start: usize, end: usize)
+    {
+        if start == end {
+            return;
+        }
+        let killed: String = self.buffer.drain(start..end).collect();
+        self.index = start;
+        match self.kill_ring.last_mut() {
+            Some(last) if self.last_was_kill => last.push_str(&killed),
+            _ => self.kill_ring.push(killed),
+        }
+        self.last_was_kill = true;
+        self.last_yank = None;
+    }
+
+    fn insert_yank(&mut self, yank_index: usize)
+    {
+        let text: Vec<char> = self.kill_ring[yank_index].chars().collect();
+        let start = self.index;
+        let text_len = text.len();
+        self.buffer.splice(start..start, text);
+        self.index = start + text_len;
+        self.last_was_kill = false;
+        self.last_yank = Some((start, self.index));
+        self.yank_index = yank_index;
+    }
+
+    /* Ctrl-Y, inserts the most recent kill */
+    pub fn yank(&mut self)
+    {
+        if self.kill_ring.is_empty() {
+            return;
+        }
+        let last = self.kill_ring.len() - 1;
+        self.insert_yank(last);
+    }
+
+    /* Alt-Y, right after a yank replaces it with the previous kill */
+    pub fn yank_pop(&mut self)
+    {
+        if let Some((start, end)) = self.last_yank {
+            self.buffer.drain(start..end);
+            self.index = start;
+            let ring_len = self.kill_ring.len();
+            let yank_index = (self.yank_index + ring_len - 1) % ring_len;
+            self.insert_yank(yank_index);
+        }
+    }
+}
+
+impl std::fmt::Display for LineBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        let s: String = self.buffer.iter().collect();
+        f.write_str(s.as_str())
+    }
+}
+
+/* ambiguous completion currently being cycled through,
+ * dropping it hides the list of candidates */
+struct CompletionState {
+    word_start: usize,
+    candidates: Vec<String>,
+    next: usize,
+    _options_win: window::JoshutoPanel,
+}
+
 pub struct JoshutoTextField {
     pub win: window::JoshutoPanel,
     pub prompt: String,
+    pub completer: Option<JoshutoCompleter>,
+    pub history: Vec<String>,
 }
 
 impl JoshutoTextField {
@@ -19,30 +314,62 @@ impl JoshutoTextField {
         JoshutoTextField {
             win,
             prompt,
+            completer: None,
+            history: Vec::new(),
         }
     }
 
+    pub fn set_history(&mut self, history: &[String])
+    {
+        self.history = history.to_vec();
+    }
+
+    pub fn set_completer(&mut self, completer: JoshutoCompleter)
+    {
+        self.completer = Some(completer);
+    }
+
+    fn display_completions(&self, candidates: &[String]) -> window::JoshutoPanel
+    {
+        /* the list and its header have to fit above the textfield */
+        let space = self.win.coords.0.saturating_sub(1);
+        let max_rows = std::cmp::min(self.win.coords.0 / 2, space);
+        let rows = if candidates.len() > max_rows {
+                max_rows
+            } else {
+                candidates.len()
+            };
+        let options_win = window::JoshutoPanel::new(rows as i32 + 1, self.win.cols,
+                (self.win.coords.0.saturating_sub(rows + 1), 0));
+        let display_vec: Vec<String> = candidates[..rows].iter()
+                .map(|s| format!("  {}", s)).collect();
+
+        options_win.move_to_top();
+        ui::display_options(&options_win, &display_vec);
+        ncurses::doupdate();
+        options_win
+    }
+
     pub fn readline_with_initial(&self, prefix: &str, suffix: &str) -> Option<String>
     {
-        let mut buf_vec: Vec<char> = Vec::with_capacity(prefix.len() + suffix.len());
-        let mut curs_x: i32 = self.prompt.len() as i32;
-        for ch in prefix.chars() {
-            let char_len = unicode_width::UnicodeWidthChar::width(ch).unwrap_or(1);
-            buf_vec.push(ch);
-            curs_x = curs_x + char_len as i32;
-        }
-        let curr_index: usize = buf_vec.len();
+        let line = LineBuffer::new(prefix, suffix);
+        ncurses::timeout(-1);
+        self.readline_(line)
+    }
 
-        for ch in suffix.chars() {
-            buf_vec.push(ch);
+    /* reads the key following an ESC, if one arrives quickly enough */
+    fn read_alt_key(&self) -> Option<i32>
+    {
+        ncurses::wtimeout(self.win.win, ESCAPE_TIMEOUT);
+        let ch = ncurses::wget_wch(self.win.win);
+        ncurses::wtimeout(self.win.win, -1);
+        match ch {
+            Some(ncurses::WchResult::Char(s)) => Some(s as i32),
+            _ => None,
         }
-        ncurses::timeout(-1);
-        let user_input = self.readline_(buf_vec, curs_x, curr_index);
-        user_input
     }
 
-    fn readline_(&self, mut buffer: Vec<(char)>, mut curs_x: i32,
-            mut curr_index: usize) -> Option<String>
+    fn readline_(&self, mut line: LineBuffer) -> Option<String>
     {
         self.win.move_to_top();
 
@@ -55,96 +382,131 @@ impl JoshutoTextField {
 
         let coord = (0, self.win.coords.1 + prompt_len);
 
+        let mut completion: Option<CompletionState> = None;
+
+        /* index into history, history.len() being the line being edited */
+        let mut history_index: usize = self.history.len();
+        let mut draft: Vec<char> = Vec::new();
+
         loop {
             ncurses::wmove(win, coord.0, coord.1 as i32);
-            {
-                let str_ch: String = buffer.iter().collect();
-                ncurses::waddstr(win, &str_ch);
-            }
-            ncurses::waddstr(win, "    ");
+            ncurses::waddstr(win, line.to_string().as_str());
+            ncurses::wclrtoeol(win);
 
+            let curs_x = coord.1 as i32 + line.cursor_width() as i32;
             ncurses::mvwchgat(win, coord.0 as i32, curs_x, 1,
                     ncurses::A_STANDOUT(), 0);
             ncurses::wrefresh(win);
 
-            let ch = ncurses::wget_wch(win).unwrap();
-            let ch = match ch {
-                    ncurses::WchResult::Char(s) => s as i32,
-                    ncurses::WchResult::KeyCode(s) => s,
+            let (ch, is_keycode) = match ncurses::wget_wch(win) {
+                    Some(ncurses::WchResult::Char(s)) => (s as i32, false),
+                    Some(ncurses::WchResult::KeyCode(s)) => (s, true),
+                    None => continue,
                 };
 
-            if ch == keymap::ESCAPE {
-                return None;
-            } else if ch == keymap::ENTER {
-                break;
-            } else if ch == ncurses::KEY_HOME {
-                if curr_index != 0 {
-                    curs_x = coord.1 as i32;
-                    curr_index = 0;
-                }
-            } else if ch == ncurses::KEY_END {
-                let buffer_len = buffer.len();
-                if curr_index != buffer_len {
-                    for i in curr_index..buffer_len {
-                        curs_x = curs_x + buffer[i] as i32;
+            if !is_keycode && ch == keymap::TAB {
+                if let Some(state) = completion.as_mut() {
+                    line.replace_before_cursor(state.word_start,
+                            state.candidates[state.next].as_str());
+                    state.next = (state.next + 1) % state.candidates.len();
+                } else if let Some(completer) = self.completer.as_ref() {
+                    let (word_start, candidates) = completer.complete(line.as_slice(), line.index());
+                    if candidates.len() == 1 {
+                        line.replace_before_cursor(word_start, candidates[0].as_str());
+                    } else if candidates.len() > 1 {
+                        let prefix = completion::common_prefix(&candidates);
+                        let next = if prefix.chars().count() > line.index() - word_start {
+                                line.replace_before_cursor(word_start, prefix.as_str());
+                                0
+                            } else {
+                                line.replace_before_cursor(word_start, candidates[0].as_str());
+                                1
+                            };
+                        let options_win = self.display_completions(&candidates);
+                        completion = Some(CompletionState {
+                            word_start,
+                            candidates,
+                            next,
+                            _options_win: options_win,
+                        });
                     }
-                    curr_index = buffer_len;
-                }
-            } else if ch == ncurses::KEY_LEFT {
-                if curr_index > 0 {
-                    curr_index = curr_index - 1;
-                    curs_x = curs_x - unicode_width::UnicodeWidthChar::width(buffer[curr_index]).unwrap_or(1) as i32;
-                }
-            } else if ch == ncurses::KEY_RIGHT {
-                let buffer_len = buffer.len();
-                if curr_index < buffer_len {
-                    curs_x = curs_x + unicode_width::UnicodeWidthChar::width(buffer[curr_index]).unwrap_or(1) as i32;
-                    curr_index = curr_index + 1;
-                }
-            } else if ch == keymap::BACKSPACE {
-                let buffer_len = buffer.len();
-                if buffer_len == 0 {
-                    continue;
                 }
+                continue;
+            }
 
-                if curr_index == buffer_len {
-                    curr_index = curr_index - 1;
-                    if let Some(ch) = buffer.pop() {
-                        curs_x = curs_x - unicode_width::UnicodeWidthChar::width(ch).unwrap_or(1) as i32;
-                    }
-                } else if curr_index > 0 {
-                    curr_index = curr_index - 1;
-                    let ch = buffer.remove(curr_index);
-                    curs_x = curs_x - unicode_width::UnicodeWidthChar::width(ch).unwrap_or(1) as i32;
-                }
-            } else if ch == ncurses::KEY_DC {
-                let buffer_len = buffer.len();
+            if completion.is_some() {
+                completion = None;
+                ncurses::update_panels();
+                ncurses::doupdate();
+            }
 
-                if buffer_len == 0 || curr_index == buffer_len {
-                    continue;
+            if is_keycode {
+                match ch {
+                    ncurses::KEY_UP if history_index > 0 => {
+                        if history_index == self.history.len() {
+                            draft = line.as_slice().to_vec();
+                        }
+                        history_index -= 1;
+                        let entry: Vec<char> = self.history[history_index].chars().collect();
+                        line.set_contents(&entry);
+                    },
+                    ncurses::KEY_DOWN if history_index < self.history.len() => {
+                        history_index += 1;
+                        if history_index == self.history.len() {
+                            line.set_contents(&draft);
+                        } else {
+                            let entry: Vec<char> = self.history[history_index].chars().collect();
+                            line.set_contents(&entry);
+                        }
+                    },
+                    ncurses::KEY_HOME => line.move_home(),
+                    ncurses::KEY_END => line.move_end(),
+                    ncurses::KEY_LEFT => line.move_left(),
+                    ncurses::KEY_RIGHT => line.move_right(),
+                    ncurses::KEY_BACKSPACE => line.backspace(),
+                    ncurses::KEY_DC => line.delete(),
+                    ncurses::KEY_ENTER => break,
+                    _ => {},
                 }
+                continue;
+            }
 
-                if curr_index > 0 {
-                    let ch = buffer.remove(curr_index);
-                    if curr_index > buffer_len {
-                        curr_index = curr_index - 1;
-                        curs_x = curs_x - unicode_width::UnicodeWidthChar::width(ch).unwrap_or(1) as i32;
-                    }
-                } else if curr_index == 0 {
-                    buffer.remove(curr_index);
+            if ch == keymap::ESCAPE {
+                match self.read_alt_key() {
+                    Some(s) if s == 'b' as i32 => line.move_word_left(),
+                    Some(s) if s == 'f' as i32 => line.move_word_right(),
+                    Some(s) if s == 'y' as i32 => line.yank_pop(),
+                    Some(_) => {},
+                    None => return None,
+                }
+            } else if ch == keymap::ENTER {
+                break;
+            } else if ch == keymap::BACKSPACE || ch == ctrl('H') {
+                line.backspace();
+            } else if ch == ctrl('A') {
+                line.move_home();
+            } else if ch == ctrl('E') {
+                line.move_end();
+            } else if ch == ctrl('B') {
+                line.move_left();
+            } else if ch == ctrl('F') {
+                line.move_right();
+            } else if ch == ctrl('D') {
+                line.delete();
+            } else if ch == ctrl('W') {
+                line.kill_word_backward();
+            } else if ch == ctrl('U') {
+                line.kill_to_start();
+            } else if ch == ctrl('K') {
+                line.kill_to_end();
+            } else if ch == ctrl('Y') {
+                line.yank();
+            } else if let Some(ch) = std::char::from_u32(ch as u32) {
+                if !ch.is_control() {
+                    line.insert(ch);
                 }
-            } else {
-                let ch = std::char::from_u32(ch as u32).unwrap();
-                let char_len = unicode_width::UnicodeWidthChar::width(ch).unwrap_or(1);
-
-                buffer.insert(curr_index, ch);
-
-                curs_x = curs_x + char_len as i32;
-                curr_index = curr_index + 1;
             }
         }
-        let user_str: String = buffer.iter().map(|ch| ch).collect();
-
-        return Some(user_str);
+        Some(line.to_string())
     }
 }
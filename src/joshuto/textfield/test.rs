@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+
+    fn type_str(line: &mut LineBuffer, s: &str) {
+        for ch in s.chars() {
+            line.insert(ch);
+        }
+    }
+
+    #[test]
+    fn initial_cursor() {
+        let line = LineBuffer::new("file", ".txt");
+        assert_eq!("file.txt", line.to_string());
+        assert_eq!(4, line.index());
+        assert_eq!(4, line.cursor_width());
+    }
+    #[test]
+    fn wide_char_cursor() {
+        let mut line = LineBuffer::new("日本", "語.txt");
+        assert_eq!(2, line.index());
+        assert_eq!(4, line.cursor_width());
+        line.move_end();
+        assert_eq!(7, line.index());
+        assert_eq!(10, line.cursor_width());
+        line.move_home();
+        line.move_right();
+        assert_eq!(2, line.cursor_width());
+        line.backspace();
+        assert_eq!("本語.txt", line.to_string());
+        assert_eq!(0, line.cursor_width());
+        type_str(&mut line, "ö");
+        assert_eq!(1, line.cursor_width());
+    }
+    #[test]
+    fn delete_at_edges() {
+        let mut line = LineBuffer::new("", "abc");
+        line.delete();
+        assert_eq!("bc", line.to_string());
+        assert_eq!(0, line.index());
+        line.backspace();
+        assert_eq!("bc", line.to_string());
+        line.move_end();
+        line.delete();
+        assert_eq!("bc", line.to_string());
+        line.backspace();
+        assert_eq!("b", line.to_string());
+        assert_eq!(1, line.index());
+    }
+    #[test]
+    fn word_motion() {
+        let mut line = LineBuffer::new("tar czf my_archive.tgz", "");
+        line.move_word_left();
+        assert_eq!(19, line.index());
+        line.move_word_left();
+        assert_eq!(8, line.index());
+        line.move_word_left();
+        line.move_word_left();
+        assert_eq!(0, line.index());
+        line.move_word_left();
+        assert_eq!(0, line.index());
+        line.move_word_right();
+        assert_eq!(3, line.index());
+        line.move_word_right();
+        assert_eq!(7, line.index());
+    }
+    #[test]
+    fn kill_and_yank() {
+        let mut line = LineBuffer::new("cd ~/src/joshuto", "");
+        line.kill_word_backward();
+        assert_eq!("cd ", line.to_string());
+        line.kill_word_backward();
+        assert_eq!("", line.to_string());
+        /* consecutive kills merge into one entry */
+        line.yank();
+        assert_eq!("cd ~/src/joshuto", line.to_string());
+        line.move_home();
+        line.move_word_right();
+        line.kill_to_end();
+        assert_eq!("cd", line.to_string());
+        line.move_home();
+        line.yank();
+        assert_eq!(" ~/src/joshutocd", line.to_string());
+        line.yank_pop();
+        assert_eq!("cd ~/src/joshutocd", line.to_string());
+    }
+    #[test]
+    fn kill_to_start() {
+        let mut line = LineBuffer::new("search", " term");
+        line.kill_to_start();
+        assert_eq!(" term", line.to_string());
+        assert_eq!(0, line.index());
+        line.move_end();
+        line.yank();
+        assert_eq!(" termsearch", line.to_string());
+    }
+    #[test]
+    fn replace_before_cursor() {
+        let mut line = LineBuffer::new("cd sr", " --flag");
+        line.replace_before_cursor(3, "src/");
+        assert_eq!("cd src/ --flag", line.to_string());
+        assert_eq!(7, line.index());
+    }
+}
@@ -0,0 +1,422 @@
+extern crate libc;
+
+use std::ffi;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::os::unix;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path;
+use std::process;
+
+use joshuto::conflict;
+use joshuto::job::JobReport;
+
+#[cfg(test)]
+mod test;
+
+/* a move across filesystems copies to a name starting with this next to
+ * the destination, and renames it into place once the copy checks out */
+const TEMP_PREFIX: &str = ".joshuto-move-";
+
+/* how the copy made by a move across filesystems is checked
+ * before the source is removed */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MoveVerify {
+    Size,
+    Contents,
+}
+
+impl MoveVerify {
+    pub fn parse(s: &str) -> Option<Self>
+    {
+        match s {
+            "size" => Some(MoveVerify::Size),
+            "contents" => Some(MoveVerify::Contents),
+            _ => None,
+        }
+    }
+}
+
+/* called with the bytes copied since the last call and between files,
+ * false stops the transfer */
+pub type Progress<'a> = dyn FnMut(u64) -> bool + 'a;
+
+/* copy a file in chunks, so progress can be reported and the copy stopped.
+ * returns false if progress asked to stop. A partly written file is removed */
+fn copy_file(source: &path::Path, destination: &path::Path, overwrite: bool, sync: bool,
+        progress: &mut Progress) -> io::Result<bool>
+{
+    const BUFFER_SIZE: usize = 64 * 1024;
+
+    let mut reader = fs::File::open(source)?;
+    let permissions = reader.metadata()?.permissions();
+    let mut writer = fs::OpenOptions::new().write(true)
+            .create(overwrite).create_new(!overwrite).truncate(overwrite)
+            .open(destination)?;
+
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let write_all = || -> io::Result<bool> {
+        loop {
+            let len = reader.read(&mut buffer)?;
+            if len == 0 {
+                break;
+            }
+            writer.write_all(&buffer[..len])?;
+            if !progress(len as u64) {
+                return Ok(false);
+            }
+        }
+        writer.set_permissions(permissions)?;
+        if sync {
+            writer.sync_all()?;
+        }
+        Ok(true)
+    };
+    let result = write_all();
+    match result {
+        Ok(true) => {},
+        _ => { let _ = fs::remove_file(destination); },
+    }
+    result
+}
+
+/* make way for something like metadata at destination: a directory can't
+ * replace a file, nor a file a directory, and a symlink can't be written over */
+pub fn clear_destination(destination: &path::Path, metadata: &fs::Metadata) -> io::Result<()>
+{
+    if let Ok(dest_metadata) = fs::symlink_metadata(destination) {
+        if dest_metadata.is_dir() && !metadata.is_dir() {
+            fs::remove_dir_all(destination)?;
+        } else if !dest_metadata.is_dir()
+                && (metadata.is_dir() || metadata.file_type().is_symlink()) {
+            fs::remove_file(destination)?;
+        }
+    }
+    Ok(())
+}
+
+fn sync_dir(path: &path::Path) -> io::Result<()>
+{
+    fs::File::open(path)?.sync_all()
+}
+
+/* copy source to destination, going through directories a file at a time so
+ * a failure only costs that file. sync makes sure every file and directory is
+ * on disk before returning. returns false if progress asked to stop */
+pub fn copy_item(source: &path::Path, destination: &path::Path, overwrite: bool, sync: bool,
+        progress: &mut Progress, report: &mut JobReport) -> bool
+{
+    let metadata = match fs::symlink_metadata(source) {
+            Ok(s) => s,
+            Err(e) => {
+                report.add(source, Err(e));
+                return true;
+            },
+        };
+    if overwrite {
+        if let Err(e) = clear_destination(destination, &metadata) {
+            report.add(source, Err(e));
+            return true;
+        }
+    }
+
+    if metadata.is_dir() {
+        let entries = fs::create_dir_all(destination).and_then(|_| fs::read_dir(source));
+        let entries = match entries {
+                Ok(s) => s,
+                Err(e) => {
+                    report.add(source, Err(e));
+                    return true;
+                },
+            };
+        report.add(source, Ok(()));
+        for entry in entries {
+            if !progress(0) {
+                return false;
+            }
+            match entry {
+                Ok(s) => {
+                    if !copy_item(&s.path(), &destination.join(s.file_name()),
+                            overwrite, sync, progress, report) {
+                        return false;
+                    }
+                },
+                Err(e) => report.add(source, Err(e)),
+            }
+        }
+        if sync {
+            if let Err(e) = sync_dir(destination) {
+                report.add(destination, Err(e));
+            }
+        }
+        true
+    } else {
+        let result = if metadata.file_type().is_symlink() {
+                fs::read_link(source)
+                    .and_then(|s| unix::fs::symlink(s, destination))
+                    .map(|_| true)
+            } else if !metadata.is_file() {
+                /* opening a fifo would block */
+                Err(io::Error::other("not a regular file"))
+            } else {
+                copy_file(source, destination, overwrite, sync, progress)
+            };
+        match result {
+            Ok(s) => {
+                report.add(source, Ok(()));
+                s
+            },
+            Err(e) => {
+                report.add(source, Err(e));
+                true
+            },
+        }
+    }
+}
+
+/* remove path and everything in it, carrying on past failures */
+pub fn remove_path(path: &path::Path, report: &mut JobReport)
+{
+    let metadata = match fs::symlink_metadata(path) {
+            Ok(s) => s,
+            Err(e) => {
+                report.add(path, Err(e));
+                return;
+            },
+        };
+    if !metadata.is_dir() {
+        report.add(path, fs::remove_file(path));
+        return;
+    }
+
+    let failed = report.errors.len();
+    match fs::read_dir(path) {
+        Ok(entries) => {
+            for entry in entries {
+                match entry {
+                    Ok(s) => remove_path(&s.path(), report),
+                    Err(e) => report.add(path, Err(e)),
+                }
+            }
+        },
+        Err(e) => report.add(path, Err(e)),
+    }
+    /* a directory left with files in it has had its failures reported */
+    if report.errors.len() == failed {
+        report.add(path, fs::remove_dir(path));
+    }
+}
+
+/* the destination's name is cut short to keep within NAME_MAX */
+fn temp_path(destination: &path::Path) -> path::PathBuf
+{
+    let mut name = format!("{}{}-", TEMP_PREFIX, process::id()).into_bytes();
+    if let Some(s) = destination.file_name() {
+        let len = s.len().min((libc::NAME_MAX as usize).saturating_sub(name.len()));
+        name.extend_from_slice(&s.as_bytes()[..len]);
+    }
+    destination.with_file_name(ffi::OsStr::from_bytes(&name))
+}
+
+fn process_alive(pid: libc::pid_t) -> bool
+{
+    let signalled = unsafe { libc::kill(pid, 0) == 0 };
+    signalled || io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
+
+/* remove what moves into dir left behind when the joshuto
+ * running them exited before they were done */
+pub fn clean_stale_temps(dir: &path::Path)
+{
+    let entries = match fs::read_dir(dir) {
+            Ok(s) => s,
+            Err(_) => return,
+        };
+    for entry in entries.filter_map(|s| s.ok()) {
+        let name = entry.file_name();
+        let pid = name.to_str()
+                .and_then(|s| s.strip_prefix(TEMP_PREFIX))
+                .and_then(|s| s.split('-').next())
+                .and_then(|s| s.parse::<libc::pid_t>().ok());
+        if pid.map(|s| !process_alive(s)).unwrap_or(false) {
+            remove_path(&entry.path(), &mut JobReport::new());
+        }
+    }
+}
+
+/* compared a chunk at a time, so large files aren't read into memory */
+pub fn files_differ(a: &path::Path, b: &path::Path) -> io::Result<bool>
+{
+    const BUFFER_SIZE: usize = 64 * 1024;
+
+    let (mut a, mut b) = (fs::File::open(a)?, fs::File::open(b)?);
+    let (mut a_buffer, mut b_buffer) = (vec![0; BUFFER_SIZE], vec![0; BUFFER_SIZE]);
+    loop {
+        let len = a.read(&mut a_buffer)?;
+        if len == 0 {
+            return Ok(b.read(&mut b_buffer[..1])? != 0);
+        }
+        b.read_exact(&mut b_buffer[..len])?;
+        if a_buffer[..len] != b_buffer[..len] {
+            return Ok(true);
+        }
+    }
+}
+
+/* check that copy holds all of source */
+pub fn verify(source: &path::Path, copy: &path::Path, mode: MoveVerify) -> io::Result<()>
+{
+    let mismatch = |what: &str| -> io::Result<()> {
+        Err(io::Error::other(format!("{} of {} doesn't match its copy", what,
+                source.to_string_lossy())))
+    };
+    let metadata = fs::symlink_metadata(source)?;
+    let copy_metadata = fs::symlink_metadata(copy)?;
+    if metadata.file_type() != copy_metadata.file_type() {
+        return mismatch("type");
+    }
+
+    if metadata.is_dir() {
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            verify(&entry.path(), &copy.join(entry.file_name()), mode)?;
+        }
+    } else if metadata.file_type().is_symlink() {
+        if fs::read_link(source)? != fs::read_link(copy)? {
+            return mismatch("target");
+        }
+    } else if metadata.len() != copy_metadata.len() {
+        return mismatch("size");
+    } else if mode == MoveVerify::Contents && files_differ(source, copy)? {
+        return mismatch("contents");
+    }
+    Ok(())
+}
+
+/* copy to a temporary name, check the copy, rename it into place and only
+ * then remove source. If anything goes wrong the copy is removed instead */
+fn move_across(source: &path::Path, destination: &path::Path, overwrite: bool,
+        mode: MoveVerify, progress: &mut Progress, report: &mut JobReport) -> bool
+{
+    let temp = temp_path(destination);
+    let failed = report.errors.len();
+    let finished = copy_item(source, &temp, false, true, progress, report);
+
+    let result = if !finished || report.errors.len() > failed {
+            Ok(false)
+        } else {
+            verify(source, &temp, mode)
+                .and_then(|_| {
+                    if !overwrite && conflict::path_exists(destination) {
+                        return Err(io::Error::new(io::ErrorKind::AlreadyExists,
+                                format!("{} already exists", destination.to_string_lossy())));
+                    }
+                    fs::rename(&temp, destination)
+                })
+                .and_then(|_| match destination.parent() {
+                    Some(s) => sync_dir(s),
+                    None => Ok(()),
+                })
+                .map(|_| true)
+        };
+    match result {
+        Ok(true) => {
+            let mut removal = JobReport::new();
+            remove_path(source, &mut removal);
+            report.errors.extend(removal.errors);
+        },
+        Ok(false) => remove_path(&temp, &mut JobReport::new()),
+        Err(e) => {
+            report.add(source, Err(e));
+            remove_path(&temp, &mut JobReport::new());
+        },
+    }
+    finished
+}
+
+/* whether source can be renamed into destination's directory,
+ * rather than copied. true if either can't be looked at */
+pub fn same_filesystem(source: &path::Path, destination: &path::Path) -> bool
+{
+    let dev = fs::symlink_metadata(source).map(|s| s.dev());
+    let dest_dev = destination.parent()
+            .map(|s| fs::metadata(s).map(|s| s.dev()));
+    match (dev, dest_dev) {
+        (Ok(s), Some(Ok(d))) => s == d,
+        _ => true,
+    }
+}
+
+/* move source to destination, merging into a directory already there if
+ * overwrite is set. returns false if progress asked to stop */
+pub fn move_item(source: &path::Path, destination: &path::Path, overwrite: bool,
+        mode: MoveVerify, progress: &mut Progress, report: &mut JobReport) -> bool
+{
+    let metadata = match fs::symlink_metadata(source) {
+            Ok(s) => s,
+            Err(e) => {
+                report.add(source, Err(e));
+                return true;
+            },
+        };
+    if overwrite {
+        if let Ok(dest_metadata) = fs::symlink_metadata(destination) {
+            if metadata.is_dir() && dest_metadata.is_dir() {
+                return merge_dir(source, destination, mode, progress, report);
+            }
+        }
+        if let Err(e) = clear_destination(destination, &metadata) {
+            report.add(source, Err(e));
+            return true;
+        }
+    }
+
+    match fs::rename(source, destination) {
+        Ok(_) => {
+            report.add(source, Ok(()));
+            progress(0)
+        },
+        Err(ref e) if e.raw_os_error() == Some(libc::EXDEV) => {
+            move_across(source, destination, overwrite, mode, progress, report)
+        },
+        Err(e) => {
+            report.add(source, Err(e));
+            true
+        },
+    }
+}
+
+/* move what is in source into destination, then remove source if it is empty */
+fn merge_dir(source: &path::Path, destination: &path::Path, mode: MoveVerify,
+        progress: &mut Progress, report: &mut JobReport) -> bool
+{
+    let entries = match fs::read_dir(source) {
+            Ok(s) => s,
+            Err(e) => {
+                report.add(source, Err(e));
+                return true;
+            },
+        };
+    let failed = report.errors.len();
+    for entry in entries {
+        if !progress(0) {
+            return false;
+        }
+        match entry {
+            Ok(s) => {
+                if !move_item(&s.path(), &destination.join(s.file_name()), true,
+                        mode, progress, report) {
+                    return false;
+                }
+            },
+            Err(e) => report.add(source, Err(e)),
+        }
+    }
+    if report.errors.len() == failed {
+        report.add(source, fs::remove_dir(source));
+    }
+    true
+}
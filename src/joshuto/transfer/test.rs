@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use joshuto::test_helpers::temp_dir;
+    use std::path::PathBuf;
+
+    #[test]
+    fn remove_path_reports_each_file() {
+        let dir = temp_dir("remove");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a"), "").unwrap();
+        fs::write(dir.join("sub/b"), "").unwrap();
+
+        let mut report = JobReport::new();
+        remove_path(&dir, &mut report);
+        assert!(!dir.exists());
+        assert_eq!((4, None), (report.files, report.summary()));
+
+        remove_path(&dir, &mut report);
+        assert_eq!(Some(String::from("1 of 5 failed")), report.summary());
+        assert_eq!(dir, report.errors[0].path);
+    }
+
+    #[test]
+    fn verify_copies() {
+        let dir = temp_dir("verify");
+        let (a, b) = (dir.join("a"), dir.join("b"));
+        fs::write(&a, "same size").unwrap();
+        fs::write(&b, "same size").unwrap();
+        assert!(verify(&a, &b, MoveVerify::Contents).is_ok());
+        fs::write(&b, "same-size").unwrap();
+        assert!(verify(&a, &b, MoveVerify::Size).is_ok());
+        assert!(verify(&a, &b, MoveVerify::Contents).is_err());
+        fs::write(&b, "other size").unwrap();
+        assert!(verify(&a, &b, MoveVerify::Size).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn move_across_leaves_one_copy() {
+        let dir = temp_dir("move-across");
+        let (source, destination) = (dir.join("src"), dir.join("dst"));
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("sub/f"), "contents").unwrap();
+
+        let mut report = JobReport::new();
+        assert!(move_across(&source, &destination, false, MoveVerify::Contents,
+                &mut |_| true, &mut report));
+        assert_eq!(None, report.summary());
+        assert!(!source.exists());
+        assert_eq!("contents", fs::read_to_string(destination.join("sub/f")).unwrap());
+
+        /* stopped part way, the source stays and the partial copy goes */
+        let mut report = JobReport::new();
+        assert!(!move_across(&destination, &source, false, MoveVerify::Size,
+                &mut |_| false, &mut report));
+        assert!(destination.join("sub/f").exists());
+        assert_eq!(vec![destination.clone()],
+            fs::read_dir(&dir).unwrap().map(|s| s.unwrap().path()).collect::<Vec<PathBuf>>());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn temp_name_fits() {
+        let dir = temp_dir("long-name");
+        let name = "x".repeat(libc::NAME_MAX as usize);
+        let (source, destination) = (dir.join("src"), dir.join(&name));
+        fs::write(&source, "contents").unwrap();
+
+        let temp = temp_path(&destination);
+        assert_eq!(libc::NAME_MAX as usize, temp.file_name().unwrap().len());
+        let mut report = JobReport::new();
+        assert!(move_across(&source, &destination, false, MoveVerify::Size,
+                &mut |_| true, &mut report));
+        assert_eq!(None, report.summary());
+        assert!(destination.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stale_temps_are_removed() {
+        let dir = temp_dir("stale");
+        /* no process has this id, it is above the kernel's pid limit */
+        let stale = dir.join(format!("{}{}-a", TEMP_PREFIX, libc::pid_t::MAX));
+        let ours = temp_path(&dir.join("b"));
+        fs::write(&stale, "").unwrap();
+        fs::write(&ours, "").unwrap();
+        fs::write(dir.join("c"), "").unwrap();
+
+        clean_stale_temps(&dir);
+        assert!(!stale.exists());
+        assert!(ours.exists() && dir.join("c").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,335 @@
+extern crate chrono;
+extern crate libc;
+extern crate xdg;
+
+use std::ffi;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::fs::DirBuilderExt;
+use std::os::unix::fs::MetadataExt;
+use std::path;
+use std::str;
+
+use joshuto::conflict;
+
+#[cfg(test)]
+mod test;
+
+/* trash directories as described by the freedesktop.org trash spec */
+
+const TRASH_INFO_HEADER: &str = "[Trash Info]";
+const TRASH_INFO_EXT: &str = ".trashinfo";
+const DELETION_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+/* names of files trashed while another with the same name is in the trash */
+const TRASH_NAME_PATTERN: &str = "{name}.{n}{ext}";
+
+/* path bytes outside of these are percent encoded in trashinfo files */
+fn is_unreserved(byte: u8) -> bool
+{
+    byte.is_ascii_alphanumeric() || b"-_.~/".contains(&byte)
+}
+
+pub fn percent_encode(path: &path::Path) -> String
+{
+    let mut encoded = String::new();
+    for byte in path.as_os_str().as_bytes() {
+        if is_unreserved(*byte) {
+            encoded.push(*byte as char);
+        } else {
+            encoded.push_str(format!("%{:02X}", byte).as_str());
+        }
+    }
+    encoded
+}
+
+/* replaces escape followed by digits digits in radix with the byte they encode */
+fn unescape(s: &str, escape: u8, digits: usize, radix: u32) -> path::PathBuf
+{
+    let bytes = s.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == escape && i + digits < bytes.len() {
+            let byte = str::from_utf8(&bytes[i + 1..i + 1 + digits]).ok()
+                    .and_then(|s| u8::from_str_radix(s, radix).ok());
+            if let Some(byte) = byte {
+                decoded.push(byte);
+                i += 1 + digits;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    path::PathBuf::from(ffi::OsString::from_vec(decoded))
+}
+
+pub fn percent_decode(s: &str) -> path::PathBuf
+{
+    unescape(s, b'%', 2, 16)
+}
+
+/* a file in the trash and where it came from */
+#[derive(Clone, Debug)]
+pub struct TrashEntry {
+    pub trash_path: path::PathBuf,
+    pub info_path: path::PathBuf,
+    pub original_path: path::PathBuf,
+    pub deletion_date: String,
+}
+
+impl TrashEntry {
+    /* put the file back where it was trashed from */
+    pub fn restore(&self) -> io::Result<()>
+    {
+        if conflict::path_exists(&self.original_path) {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists,
+                    format!("{} already exists", self.original_path.to_string_lossy())));
+        }
+        if let Some(parent) = self.original_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&self.trash_path, &self.original_path)?;
+        fs::remove_file(&self.info_path)
+    }
+
+    /* remove the file for good */
+    pub fn delete(&self) -> io::Result<()>
+    {
+        if let Ok(metadata) = fs::symlink_metadata(&self.trash_path) {
+            if metadata.is_dir() {
+                fs::remove_dir_all(&self.trash_path)?;
+            } else {
+                fs::remove_file(&self.trash_path)?;
+            }
+        }
+        fs::remove_file(&self.info_path)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrashDir {
+    /* holds the files and info directories */
+    pub path: path::PathBuf,
+    /* top directory of the mount this trash is on, None for the home trash,
+     * whose trashinfo files hold absolute paths */
+    pub topdir: Option<path::PathBuf>,
+}
+
+impl TrashDir {
+    pub fn new(path: path::PathBuf, topdir: Option<path::PathBuf>) -> Self
+    {
+        TrashDir {
+            path,
+            topdir,
+        }
+    }
+
+    /* $XDG_DATA_HOME/Trash */
+    pub fn home() -> io::Result<Self>
+    {
+        match xdg::BaseDirectories::new() {
+            Ok(s) => Ok(TrashDir::new(s.get_data_home().join("Trash"), None)),
+            Err(e) => Err(io::Error::other(e.to_string())),
+        }
+    }
+
+    fn files_dir(&self) -> path::PathBuf
+    {
+        self.path.join("files")
+    }
+
+    fn info_dir(&self) -> path::PathBuf
+    {
+        self.path.join("info")
+    }
+
+    fn create(&self) -> io::Result<()>
+    {
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true).mode(0o700);
+        builder.create(self.files_dir())?;
+        builder.create(self.info_dir())
+    }
+
+    /* the info file is created first, so the name is reserved
+     * before anything is moved */
+    fn reserve_name(&self, file_name: &ffi::OsStr)
+            -> io::Result<(ffi::OsString, path::PathBuf, fs::File)>
+    {
+        let mut n: usize = 0;
+        loop {
+            let name = match n {
+                    0 => file_name.to_os_string(),
+                    n => conflict::rename_with_pattern(file_name, TRASH_NAME_PATTERN, n),
+                };
+            n += 1;
+            if conflict::path_exists(&self.files_dir().join(&name)) {
+                continue;
+            }
+            let mut info_name = name.clone();
+            info_name.push(TRASH_INFO_EXT);
+            let info_path = self.info_dir().join(info_name);
+            match fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                Ok(file) => return Ok((name, info_path, file)),
+                Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /* move path, which must be on the same filesystem, into the trash */
+    pub fn trash(&self, path: &path::Path) -> io::Result<TrashEntry>
+    {
+        let file_name = match path.file_name() {
+                Some(s) => s,
+                None => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                            format!("Can't trash {}", path.to_string_lossy())));
+                },
+            };
+        self.create()?;
+
+        let (name, info_path, mut info_file) = self.reserve_name(file_name)?;
+        let info_original = match self.topdir.as_ref() {
+                Some(topdir) => path.strip_prefix(topdir).unwrap_or(path),
+                None => path,
+            };
+        let deletion_date = chrono::Local::now().format(DELETION_DATE_FORMAT).to_string();
+        let trash_path = self.files_dir().join(name);
+
+        let result = write!(info_file, "{}\nPath={}\nDeletionDate={}\n", TRASH_INFO_HEADER,
+                    percent_encode(info_original), deletion_date)
+                .and_then(|_| fs::rename(path, &trash_path));
+        if let Err(e) = result {
+            let _ = fs::remove_file(&info_path);
+            return Err(e);
+        }
+        Ok(TrashEntry {
+            trash_path,
+            info_path,
+            original_path: path.to_path_buf(),
+            deletion_date,
+        })
+    }
+
+    fn parse_info(&self, info_path: &path::Path) -> Option<TrashEntry>
+    {
+        let contents = fs::read_to_string(info_path).ok()?;
+        let mut lines = contents.lines();
+        if lines.next()?.trim() != TRASH_INFO_HEADER {
+            return None;
+        }
+        let mut original_path: Option<path::PathBuf> = None;
+        let mut deletion_date = String::new();
+        for line in lines {
+            if let Some(s) = line.strip_prefix("Path=") {
+                original_path = Some(percent_decode(s));
+            } else if let Some(s) = line.strip_prefix("DeletionDate=") {
+                deletion_date = s.to_string();
+            }
+        }
+        let mut original_path = original_path?;
+        if let Some(topdir) = self.topdir.as_ref() {
+            if original_path.is_relative() {
+                original_path = topdir.join(original_path);
+            }
+        }
+
+        /* names needn't be utf-8 */
+        let name = info_path.file_name()?.as_bytes().strip_suffix(TRASH_INFO_EXT.as_bytes())?;
+        Some(TrashEntry {
+            trash_path: self.files_dir().join(ffi::OsStr::from_bytes(name)),
+            info_path: info_path.to_path_buf(),
+            original_path,
+            deletion_date,
+        })
+    }
+
+    /* everything in this trash, oldest first */
+    pub fn entries(&self) -> Vec<TrashEntry>
+    {
+        let mut entries: Vec<TrashEntry> = match fs::read_dir(self.info_dir()) {
+                Ok(dir) => {
+                    dir.filter_map(|s| s.ok())
+                        .map(|s| s.path())
+                        .filter(|s| s.as_os_str().as_bytes().ends_with(TRASH_INFO_EXT.as_bytes()))
+                        .filter_map(|s| self.parse_info(&s))
+                        .collect()
+                },
+                Err(_) => Vec::new(),
+            };
+        entries.sort_by(|a, b| a.deletion_date.cmp(&b.deletion_date));
+        entries
+    }
+}
+
+fn uid() -> u32
+{
+    unsafe { libc::getuid() }
+}
+
+/* highest directory above path on the same filesystem */
+fn mount_topdir(path: &path::Path, dev: u64) -> path::PathBuf
+{
+    let mut topdir = path.to_path_buf();
+    for ancestor in path.ancestors().skip(1) {
+        match fs::metadata(ancestor) {
+            Ok(s) if s.dev() == dev => topdir = ancestor.to_path_buf(),
+            _ => break,
+        }
+    }
+    topdir
+}
+
+/* $topdir/.Trash/$uid if the admin made $topdir/.Trash, else $topdir/.Trash-$uid */
+fn topdir_trash(topdir: &path::Path) -> TrashDir
+{
+    let admin_trash = topdir.join(".Trash");
+    if let Ok(metadata) = fs::symlink_metadata(&admin_trash) {
+        const STICKY_BIT: u32 = 0o1000;
+        if metadata.is_dir() && metadata.mode() & STICKY_BIT != 0 {
+            return TrashDir::new(admin_trash.join(uid().to_string()),
+                    Some(topdir.to_path_buf()));
+        }
+    }
+    TrashDir::new(topdir.join(format!(".Trash-{}", uid())), Some(topdir.to_path_buf()))
+}
+
+/* the trash path goes to: the home trash if it is on the same filesystem,
+ * otherwise the trash at the top of path's mount */
+pub fn trash_dir_for(path: &path::Path) -> io::Result<TrashDir>
+{
+    let home_trash = TrashDir::home()?;
+    home_trash.create()?;
+    let dev = fs::symlink_metadata(path)?.dev();
+    if fs::metadata(&home_trash.path)?.dev() == dev {
+        return Ok(home_trash);
+    }
+    let parent = path.parent().unwrap_or(path);
+    Ok(topdir_trash(&mount_topdir(parent, dev)))
+}
+
+/* the home trash and the trash directories of mounted filesystems */
+pub fn trash_dirs() -> Vec<TrashDir>
+{
+    let mut trash_dirs: Vec<TrashDir> = Vec::new();
+    if let Ok(s) = TrashDir::home() {
+        trash_dirs.push(s);
+    }
+    let mounts = fs::read_to_string("/proc/mounts").unwrap_or_default();
+    for mount in mounts.lines() {
+        if let Some(mount_point) = mount.split_whitespace().nth(1) {
+            /* /proc/mounts escapes spaces and such as octal, like \040 */
+            let topdir = unescape(mount_point, b'\\', 3, 8);
+            let trash_dir = topdir_trash(&topdir);
+            if trash_dir.info_dir().is_dir() && !trash_dirs.contains(&trash_dir) {
+                trash_dirs.push(trash_dir);
+            }
+        }
+    }
+    trash_dirs
+}
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use joshuto::test_helpers::temp_dir;
+    use std::path::PathBuf;
+
+    #[test]
+    fn percent_encoding() {
+        let path = path::Path::new("/tmp/my file%.txt");
+        assert_eq!("/tmp/my%20file%25.txt", percent_encode(path));
+        assert_eq!(path, percent_decode("/tmp/my%20file%25.txt").as_path());
+        assert_eq!(path::Path::new("/tmp/100%"), percent_decode("/tmp/100%").as_path());
+        assert_eq!(path::Path::new("/tmp/é"), percent_decode("/tmp/%C3%A9").as_path());
+    }
+
+    #[test]
+    fn trash_and_restore() {
+        let dir = temp_dir("trash");
+        let trash_dir = TrashDir::new(dir.join("Trash"), None);
+        let file = dir.join("a file.txt");
+        fs::write(&file, "first").unwrap();
+        let first = trash_dir.trash(&file).unwrap();
+        fs::write(&file, "second").unwrap();
+        let second = trash_dir.trash(&file).unwrap();
+        assert!(!file.exists());
+        assert_eq!(dir.join("Trash/files/a file.txt"), first.trash_path);
+        assert_eq!(dir.join("Trash/files/a file.1.txt"), second.trash_path);
+
+        let entries = trash_dir.entries();
+        assert_eq!(2, entries.len());
+        assert!(entries.iter().all(|s| s.original_path == file));
+
+        first.restore().unwrap();
+        assert_eq!("first", fs::read_to_string(&file).unwrap());
+        assert!(second.restore().is_err());
+        second.delete().unwrap();
+        assert!(trash_dir.entries().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn non_utf8_names() {
+        let dir = temp_dir("trash-bytes");
+        let trash_dir = TrashDir::new(dir.join("Trash"), None);
+        let file = dir.join(std::ffi::OsStr::from_bytes(b"caf\xe9"));
+        fs::write(&file, "").unwrap();
+        let entry = trash_dir.trash(&file).unwrap();
+
+        let entries = trash_dir.entries();
+        assert_eq!(1, entries.len());
+        assert_eq!(file, entries[0].original_path);
+        assert_eq!(entry.trash_path, entries[0].trash_path);
+        entries[0].restore().unwrap();
+        assert!(file.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn topdir_relative_paths() {
+        let dir = temp_dir("topdir");
+        let trash_dir = TrashDir::new(dir.join(".Trash-1000"), Some(dir.clone()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        let file = dir.join("sub/b");
+        fs::write(&file, "").unwrap();
+        let entry = trash_dir.trash(&file).unwrap();
+        let info = fs::read_to_string(&entry.info_path).unwrap();
+        assert!(info.starts_with("[Trash Info]\nPath=sub/b\nDeletionDate="));
+        assert_eq!(vec![file.clone()],
+            trash_dir.entries().iter().map(|s| s.original_path.clone()).collect::<Vec<PathBuf>>());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
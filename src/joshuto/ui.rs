@@ -5,6 +5,7 @@ use std::fs;
 use std::time;
 
 use joshuto::config::JoshutoColorTheme;
+use joshuto::config::JoshutoTheme;
 use joshuto::context::JoshutoContext;
 use joshuto::structs;
 use joshuto::unix;
@@ -38,9 +39,10 @@ pub fn init_ncurses()
     ncurses::refresh();
 }
 
-fn process_theme()
+pub fn process_theme()
 {
-    for pair in theme_t.colorpair.iter() {
+    let theme = theme_t.read().unwrap();
+    for pair in theme.colorpair.iter() {
         ncurses::init_pair(pair.id, pair.fg, pair.bg);
     }
 
@@ -82,6 +84,22 @@ pub fn wprint_msg(win: &window::JoshutoPanel, msg: &str)
     ncurses::wnoutrefresh(win.win);
 }
 
+/* right aligned so the file status stays readable */
+pub fn wprint_pending(win: &window::JoshutoPanel, register: Option<char>, count: Option<usize>)
+{
+    let mut pending_str = String::new();
+    if let Some(s) = register {
+        pending_str.push('"');
+        pending_str.push(s);
+    }
+    if let Some(s) = count {
+        pending_str.push_str(s.to_string().as_str());
+    }
+    let col = (win.cols - pending_str.len() as i32 - 1).max(0);
+    ncurses::mvwaddstr(win.win, 0, col, pending_str.as_str());
+    ncurses::wnoutrefresh(win.win);
+}
+
 pub fn wprint_err(win: &window::JoshutoPanel, msg: &str)
 {
     ncurses::werase(win.win);
@@ -226,11 +244,15 @@ pub fn redraw_tab_view(win: &window::JoshutoPanel, context: &JoshutoContext)
 pub fn draw_progress_bar(win: &window::JoshutoPanel, percentage: f32)
 {
     let cols: i32 = (win.cols as f32 * percentage) as i32;
+    let theme = theme_t.read().unwrap();
+    /* clear the bar left by a job further along */
+    ncurses::mvwchgat(win.win, 0, 0, -1, ncurses::A_NORMAL(), 0);
     ncurses::mvwchgat(win.win, 0, 0, cols, ncurses::A_STANDOUT(),
-            theme_t.selection.colorpair);
+            theme.selection.colorpair);
 }
 
-pub fn get_theme_attr(mut attr: ncurses::attr_t, entry: &structs::JoshutoDirEntry) -> ((usize, &str), ncurses::attr_t, i16)
+pub fn get_theme_attr<'a>(curr_theme: &'a JoshutoTheme, mut attr: ncurses::attr_t,
+        entry: &structs::JoshutoDirEntry) -> ((usize, &'a str), ncurses::attr_t, i16)
 {
     use std::os::unix::fs::FileTypeExt;
     use std::os::unix::fs::PermissionsExt;
@@ -240,42 +262,42 @@ pub fn get_theme_attr(mut attr: ncurses::attr_t, entry: &structs::JoshutoDirEntr
 
     let file_type = &entry.metadata.file_type;
     if entry.selected {
-        theme = &theme_t.selection;
-        colorpair = theme_t.selection.colorpair;
+        theme = &curr_theme.selection;
+        colorpair = curr_theme.selection.colorpair;
     } else if file_type.is_dir() {
-        theme = &theme_t.directory;
-        colorpair = theme_t.directory.colorpair;
+        theme = &curr_theme.directory;
+        colorpair = curr_theme.directory.colorpair;
     } else if file_type.is_symlink() {
-        theme = &theme_t.link;
-        colorpair = theme_t.link.colorpair;
+        theme = &curr_theme.link;
+        colorpair = curr_theme.link.colorpair;
     } else if file_type.is_block_device() {
-        theme = &theme_t.socket;
-        colorpair = theme_t.link.colorpair;
+        theme = &curr_theme.socket;
+        colorpair = curr_theme.link.colorpair;
     } else if file_type.is_char_device() {
-        theme = &theme_t.socket;
-        colorpair = theme_t.link.colorpair;
+        theme = &curr_theme.socket;
+        colorpair = curr_theme.link.colorpair;
     } else if file_type.is_fifo() {
-        theme = &theme_t.socket;
-        colorpair = theme_t.link.colorpair;
+        theme = &curr_theme.socket;
+        colorpair = curr_theme.link.colorpair;
     } else if file_type.is_socket() {
-        theme = &theme_t.socket;
-        colorpair = theme_t.link.colorpair;
+        theme = &curr_theme.socket;
+        colorpair = curr_theme.link.colorpair;
     } else {
         let mode = entry.metadata.permissions.mode();
         if unix::is_executable(mode) {
-            theme = &theme_t.executable;
-            colorpair = theme_t.executable.colorpair;
+            theme = &curr_theme.executable;
+            colorpair = curr_theme.executable.colorpair;
         } else if let Some(ext) = entry.file_name_as_string.rfind('.') {
             let extension: &str = &entry.file_name_as_string[ext+1..];
-            if let Some(s) = theme_t.ext.get(extension) {
+            if let Some(s) = curr_theme.ext.get(extension) {
                 theme = &s;
                 colorpair = theme.colorpair;
             } else {
-                theme = &theme_t.regular;
+                theme = &curr_theme.regular;
                 colorpair = theme.colorpair;
             }
         } else {
-            theme = &theme_t.regular;
+            theme = &curr_theme.regular;
             colorpair = theme.colorpair;
         }
     }
@@ -297,7 +319,7 @@ pub fn get_theme_attr(mut attr: ncurses::attr_t, entry: &structs::JoshutoDirEntr
     (prefix, attr, colorpair)
 }
 
-fn file_size_to_string_detailed(mut file_size: f64) -> String
+pub fn file_size_to_string_detailed(mut file_size: f64) -> String
 {
     const FILE_UNITS: [&str ; 6] = ["B", "KB", "MB", "GB", "TB", "EB"];
     const CONV_RATE: f64 = 1024.0;
@@ -319,7 +341,7 @@ fn file_size_to_string_detailed(mut file_size: f64) -> String
     }
 }
 
-fn file_mtime_to_string(mtime: time::SystemTime) -> String
+pub fn file_mtime_to_string(mtime: time::SystemTime) -> String
 {
     const MTIME_FORMATTING: &str = "%Y-%m-%d %H:%M";
 
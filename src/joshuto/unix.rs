@@ -6,6 +6,9 @@ use std::process;
 
 use joshuto::config::mimetype;
 
+#[cfg(test)]
+mod test;
+
 // pub const BITMASK  : u32 = 0o170000;
 pub const S_IFSOCK : u32 = 0o140000;   /* socket */
 pub const S_IFLNK  : u32 = 0o120000;   /* symbolic link */
@@ -108,6 +111,30 @@ pub fn set_mode(path: &path::Path, mode: libc::mode_t) {
     }
 }
 
+/* path relative to the directory base, both absolute */
+pub fn relative_path(path: &path::Path, base: &path::Path) -> path::PathBuf
+{
+    let mut path_iter = path.components().peekable();
+    let mut base_iter = base.components().peekable();
+    while let (Some(p), Some(b)) = (path_iter.peek(), base_iter.peek()) {
+        if p != b {
+            break;
+        }
+        path_iter.next();
+        base_iter.next();
+    }
+
+    let mut relative = path::PathBuf::new();
+    for _ in base_iter {
+        relative.push("..");
+    }
+    relative.extend(path_iter);
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
+
 pub fn open_with_entry(paths: &Vec<path::PathBuf>, entry: &mimetype::JoshutoMimetypeEntry)
 {
     let program = entry.program.clone();
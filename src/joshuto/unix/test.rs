@@ -0,0 +1,14 @@
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+
+    #[test]
+    fn relative_paths() {
+        let relative = |a: &str, b: &str| relative_path(path::Path::new(a), path::Path::new(b));
+        assert_eq!(path::Path::new("a/file"), relative("/home/a/file", "/home"));
+        assert_eq!(path::Path::new("../b/file"), relative("/home/b/file", "/home/a"));
+        assert_eq!(path::Path::new("../../../etc/file"), relative("/etc/file", "/home/a/b"));
+        assert_eq!(path::Path::new(".."), relative("/home", "/home/a"));
+        assert_eq!(path::Path::new("."), relative("/home/a", "/home/a"));
+    }
+}
@@ -3,6 +3,8 @@ extern crate ncurses;
 use joshuto::structs;
 use joshuto::ui;
 
+use joshuto::theme_t;
+
 #[cfg(test)]
 mod test;
 
@@ -140,6 +142,7 @@ impl JoshutoPanel {
         let (start, end) = (dirlist.pagestate.start, dirlist.pagestate.end);
 
         let curr_index = dirlist.index as usize;
+        let theme = theme_t.read().unwrap();
 
         for i in start..end {
             let coord: (i32, i32) = (i as i32 - start as i32, 0);
@@ -151,7 +154,7 @@ impl JoshutoPanel {
             if i == curr_index {
                 attr = attr | ncurses::A_STANDOUT();
             }
-            let attrs = ui::get_theme_attr(attr, entry);
+            let attrs = ui::get_theme_attr(&theme, attr, entry);
 
             draw_func(win, entry, attrs.0, coord);
 
@@ -7,6 +7,9 @@ extern crate serde_derive;
 extern crate toml;
 extern crate xdg;
 
+use std::path;
+use std::process;
+
 mod joshuto;
 
 const PROGRAM_NAME: &str = "joshuto";
@@ -14,22 +17,112 @@ const CONFIG_FILE: &str = "joshuto.toml";
 const MIMETYPE_FILE: &str = "mimetype.toml";
 const KEYMAP_FILE: &str = "keymap.toml";
 const THEME_FILE: &str = "theme.toml";
+/* command lines run at startup, one per line */
+const RC_FILE: &str = "joshuto.rc";
+/* directory of config files layered over the system and user config */
+const CONFIG_DIR_ENV: &str = "JOSHUTO_CONFIG_DIR";
+
+fn absolute_path(s: &std::ffi::OsStr) -> path::PathBuf
+{
+    match std::env::current_dir() {
+        Ok(dir) => dir.join(s),
+        Err(_) => path::PathBuf::from(s),
+    }
+}
 
 fn main()
 {
-    let args: Vec<String> = std::env::args().collect();
-    for arg in &args {
-        if arg.as_str() == "-v" {
-            println!("{}", crate_version!());
-            return
+    let matches = clap::App::new(PROGRAM_NAME)
+        .version(crate_version!())
+        .version_short("v")
+        .about(crate_description!())
+        .arg(clap::Arg::with_name("path")
+            .multiple(true)
+            .help("Directories to open, one tab each"))
+        .arg(clap::Arg::with_name("config-dir")
+            .long("config-dir")
+            .takes_value(true)
+            .value_name("DIR")
+            .help("Directory of config files overriding the system and user config"))
+        .arg(clap::Arg::with_name("check-config")
+            .long("check-config")
+            .help("Check the config files for errors and exit"))
+        .arg(clap::Arg::with_name("dump-default-config")
+            .long("dump-default-config")
+            .takes_value(true)
+            .value_name("DIR")
+            .help("Write the built-in config files into DIR and exit"))
+        .arg(clap::Arg::with_name("cmd")
+            .long("cmd")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("COMMAND")
+            .help("Command to run after startup, can be given more than once"))
+        .arg(clap::Arg::with_name("choosedir")
+            .long("choosedir")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("Write the current directory to FILE on quit"))
+        .arg(clap::Arg::with_name("choosefiles")
+            .long("choosefiles")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("Write the selected files to FILE and quit when opening files"))
+        .arg(clap::Arg::with_name("null")
+            .long("null")
+            .short("0")
+            .requires("choosefiles")
+            .help("Separate the files written by --choosefiles with NUL instead of newlines"))
+        .get_matches();
+
+    /* before anything reads the config, and made absolute
+     * as joshuto changes directories */
+    let config_dir = matches.value_of_os("config-dir").map(std::ffi::OsString::from)
+            .or_else(|| std::env::var_os(CONFIG_DIR_ENV));
+    if let Some(s) = config_dir {
+        std::env::set_var(CONFIG_DIR_ENV, absolute_path(&s));
+    }
+
+    if let Some(s) = matches.value_of_os("dump-default-config") {
+        if let Err(e) = joshuto::config::parse::dump_default_config(path::Path::new(s)) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return
+    }
+    if matches.is_present("check-config") {
+        let errors = joshuto::config::parse::check_config();
+        for e in &errors {
+            eprintln!("{}", e);
         }
+        if !errors.is_empty() {
+            eprintln!("{} error(s) found", errors.len());
+            process::exit(1);
+        }
+        println!("Config OK");
+        return
     }
 
-    let config = joshuto::config::JoshutoConfig::get_config();
-//    println!("{:#?}", config);
+    let args = joshuto::JoshutoArgs {
+        paths: match matches.values_of_os("path") {
+            Some(s) => s.map(path::PathBuf::from).collect(),
+            None => Vec::new(),
+        },
+        commands: match matches.values_of("cmd") {
+            Some(s) => s.map(String::from).collect(),
+            None => Vec::new(),
+        },
+        /* joshuto changes directories as it runs */
+        choosedir: matches.value_of_os("choosedir").map(absolute_path),
+        choosefiles: matches.value_of_os("choosefiles").map(absolute_path),
+        choose_null: matches.is_present("null"),
+    };
 
-    let keymap = joshuto::config::JoshutoKeymap::get_config();
+    let mut config_errors = Vec::new();
+    let (config, keymap) = joshuto::load_config(&mut config_errors);
+//    println!("{:#?}", config);
 //    println!("{:#?}", keymap);
 
-    joshuto::run(config, keymap);
+    joshuto::run(config, keymap, config_errors, args);
 }